@@ -0,0 +1,198 @@
+use anyhow::{anyhow, Result};
+use aoc::input::{Inputs, Variant};
+use aoc::{Day, DAYS};
+use std::env;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "usage: cargo bench --bench calendar -- [--report] [--budget <ms>] [day...]";
+
+/// Timings of repeated runs of a single step.
+struct Sample {
+    runs: Vec<Duration>,
+}
+
+impl Sample {
+    /// Runs `step` once to warm up, then repeatedly until `budget` is spent or enough runs exist.
+    fn measure<T>(budget: Duration, mut step: impl FnMut() -> Result<T>) -> Result<Self> {
+        black_box(step()?);
+
+        let mut runs = vec![];
+        let started = Instant::now();
+        while runs.len() < 3 || (started.elapsed() < budget && runs.len() < 1000) {
+            let start = Instant::now();
+            black_box(step()?);
+            runs.push(start.elapsed());
+        }
+
+        runs.sort();
+        Ok(Sample { runs })
+    }
+
+    fn median(&self) -> Duration {
+        self.runs[self.runs.len() / 2]
+    }
+
+    fn min(&self) -> Duration {
+        self.runs[0]
+    }
+
+    fn max(&self) -> Duration {
+        self.runs[self.runs.len() - 1]
+    }
+}
+
+struct Measurement {
+    day: u8,
+    variant: Variant,
+    parse: Sample,
+    part_one: Result<Sample>,
+    part_two: Result<Sample>,
+}
+
+impl Measurement {
+    fn steps(&self) -> [(&str, Result<&Sample, String>); 3] {
+        fn step(sample: &Result<Sample>) -> Result<&Sample, String> {
+            sample
+                .as_ref()
+                .map_err(|error| format!("failed: {:#}", error))
+        }
+
+        [
+            ("parse", Ok(&self.parse)),
+            ("part 1", step(&self.part_one)),
+            ("part 2", step(&self.part_two)),
+        ]
+    }
+
+    fn total(&self) -> Duration {
+        self.steps()
+            .iter()
+            .filter_map(|(_, sample)| sample.as_ref().ok())
+            .map(|sample| sample.median())
+            .sum()
+    }
+}
+
+fn measure(day: &Day, variant: Variant, input: &str, budget: Duration) -> Result<Measurement> {
+    let parse = Sample::measure(budget, || day.parse(input))?;
+    let parsed = day.parse(input)?;
+    let part_one = Sample::measure(budget, || parsed.part_one());
+    let part_two = Sample::measure(budget, || parsed.part_two());
+
+    Ok(Measurement {
+        day: day.number(),
+        variant,
+        parse,
+        part_one,
+        part_two,
+    })
+}
+
+/// The examples present on disk followed by the actual input.
+fn variants(inputs: &Inputs, day: u8) -> Vec<Variant> {
+    (1..)
+        .map(Variant::Example)
+        .take_while(|&variant| inputs.path(day, variant).exists())
+        .chain([Variant::Actual])
+        .filter(|&variant| inputs.path(day, variant).exists())
+        .collect()
+}
+
+fn detail(measurement: &Measurement) {
+    for (step, sample) in measurement.steps() {
+        let label = format!(
+            "day {:02} {:<9} {:<6}",
+            measurement.day,
+            measurement.variant.to_string(),
+            step
+        );
+
+        match sample {
+            Ok(sample) => println!(
+                "{} median {:>12?}  min {:>12?}  max {:>12?}  ({} runs)",
+                label,
+                sample.median(),
+                sample.min(),
+                sample.max(),
+                sample.runs.len()
+            ),
+            Err(error) => println!("{} {}", label, error),
+        }
+    }
+}
+
+fn report(measurements: &[Measurement]) {
+    let mut actual: Vec<&Measurement> = measurements
+        .iter()
+        .filter(|measurement| measurement.variant == Variant::Actual)
+        .collect();
+    actual.sort_by_key(|measurement| std::cmp::Reverse(measurement.total()));
+
+    // Day 1 streams its input line by line, so its parse step only copies the input.
+    println!(
+        "{:>3}  {:>12}  {:>12}  {:>12}  {:>12}",
+        "day", "parse", "part 1", "part 2", "total"
+    );
+    for measurement in &actual {
+        let [parse, part_one, part_two] = measurement.steps().map(|(_, sample)| {
+            sample.map_or_else(|_| "failed".to_string(), |s| format!("{:?}", s.median()))
+        });
+        println!(
+            "{:>3}  {:>12}  {:>12}  {:>12}  {:>12?}",
+            measurement.day,
+            parse,
+            part_one,
+            part_two,
+            measurement.total()
+        );
+    }
+
+    let total: Duration = actual.iter().map(|measurement| measurement.total()).sum();
+    println!("calendar total {:?} across {} days", total, actual.len());
+}
+
+fn main() -> Result<()> {
+    let mut report_mode = false;
+    let mut budget = Duration::from_millis(200);
+    let mut days: Vec<u8> = vec![];
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bench" => {}
+            "--report" => report_mode = true,
+            "--budget" => {
+                let ms = args.next().and_then(|ms| ms.parse::<u64>().ok());
+                budget = Duration::from_millis(ms.ok_or_else(|| anyhow!(USAGE))?);
+            }
+            day => days.push(
+                day.parse::<u8>()
+                    .map_err(|_| anyhow!("invalid day {:?}\n{}", day, USAGE))?,
+            ),
+        }
+    }
+
+    let inputs = Inputs::default();
+    let mut measurements = vec![];
+
+    for day in DAYS
+        .iter()
+        .filter(|day| days.is_empty() || days.contains(&day.number()))
+    {
+        for variant in variants(&inputs, day.number()) {
+            let input = inputs.load(day.number(), variant)?;
+            let measurement = measure(day, variant, &input, budget)?;
+            if !report_mode {
+                detail(&measurement);
+            }
+            measurements.push(measurement);
+        }
+    }
+
+    if report_mode {
+        report(&measurements);
+    }
+
+    Ok(())
+}
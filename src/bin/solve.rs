@@ -0,0 +1,87 @@
+use anyhow::{bail, Context, Result};
+use aoc::input::{load, normalize, Variant};
+use aoc::{Answer, Parsed};
+use std::io::Read;
+use std::time::{Duration, Instant};
+use std::{env, fs, io};
+
+const USAGE: &str = "usage: solve <day> [1|2|both] [actual|example[:N]|<path>|-]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    One,
+    Two,
+    Both,
+}
+
+impl TryFrom<&str> for Part {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            "both" => Ok(Part::Both),
+            _ => bail!("unknown part {:?}\n{}", value, USAGE),
+        }
+    }
+}
+
+fn read(day: u8, source: Option<&str>) -> Result<String> {
+    match source {
+        None => load(day, Variant::Actual),
+        Some("-") => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .context("couldn't read stdin")?;
+            Ok(normalize(&input))
+        }
+        Some(source) => match Variant::try_from(source) {
+            Ok(variant) => load(day, variant),
+            Err(_) => fs::read_to_string(source)
+                .map(|input| normalize(&input))
+                .with_context(|| format!("couldn't read {}", source)),
+        },
+    }
+}
+
+fn timed<T>(f: impl FnOnce() -> Result<T>) -> Result<(T, Duration)> {
+    let start = Instant::now();
+    let result = f()?;
+    Ok((result, start.elapsed()))
+}
+
+fn report(label: &str, parsed: &dyn Parsed, part: fn(&dyn Parsed) -> Result<Answer>) -> Result<()> {
+    let (answer, elapsed) = timed(|| part(parsed))?;
+    println!("{}: {} ({:?})", label, answer, elapsed);
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let number = args
+        .first()
+        .context(USAGE)?
+        .parse::<u8>()
+        .with_context(|| format!("invalid day {:?}", args[0]))?;
+    let day = aoc::day(number).with_context(|| format!("day {} is not implemented", number))?;
+    let part = args
+        .get(1)
+        .map_or(Ok(Part::Both), |part| Part::try_from(part.as_str()))?;
+    let input = read(number, args.get(2).map(|source| source.as_str()))?;
+
+    let (parsed, elapsed) = timed(|| day.parse(&input))?;
+    println!("day {} parsed ({:?})", day.number(), elapsed);
+
+    if part != Part::Two {
+        report("part 1", parsed.as_ref(), |parsed| parsed.part_one())?;
+    }
+
+    if part != Part::One {
+        report("part 2", parsed.as_ref(), |parsed| parsed.part_two())?;
+    }
+
+    Ok(())
+}
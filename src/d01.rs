@@ -1,60 +1,189 @@
-use anyhow::Result;
+use crate::{Answer, Solution};
+use aho_corasick::AhoCorasick;
+use anyhow::{bail, Context, Result};
 use std::collections::HashMap;
-use suffix::SuffixTable;
+use std::io::{BufRead, ErrorKind};
 
-pub fn calibrate(input: &str, tokens: &HashMap<&str, char>) -> Result<Vec<u32>> {
-    let mut first: Option<(char, u32)> = None;
-    let mut last: Option<(char, u32)> = None;
-    let mut values: Vec<u32> = Vec::new();
-    for line in input.split('\n') {
-        let st = SuffixTable::new(line);
-
-        for (token, value) in tokens.iter() {
-            let mut positions = st.positions(token).to_owned();
-            positions.sort();
-
-            if let Some(lo) = positions.first() {
-                if let Some((_, prior)) = first {
-                    if lo < &prior {
-                        first = Option::Some((*value, *lo));
-                    }
-                } else {
-                    first = Option::Some((*value, *lo));
-                }
-            }
+/// The values of the first and last tokens on a line, ordered by where each token starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Matches {
+    pub first: char,
+    pub last: char,
+}
+
+/// Finds every token on a line in a single pass, including tokens that overlap such as "oneight".
+pub struct Scanner {
+    automaton: AhoCorasick,
+    values: Vec<char>,
+}
+
+impl Scanner {
+    pub fn new(tokens: &HashMap<&str, char>) -> Result<Self> {
+        if tokens.keys().any(|token| token.is_empty()) {
+            bail!("tokens must not be empty");
+        }
 
-            if let Some(hi) = positions.last() {
-                if let Some((_, prior)) = last {
-                    if hi > &prior {
-                        last = Option::Some((*value, *hi));
-                    }
-                } else {
-                    last = Option::Some((*value, *hi));
-                }
+        let (patterns, values): (Vec<&str>, Vec<char>) = tokens.iter().unzip();
+        Ok(Scanner {
+            automaton: AhoCorasick::new(patterns)?,
+            values,
+        })
+    }
+
+    pub fn scan(&self, line: &str) -> Option<Matches> {
+        let mut first: Option<(usize, char)> = None;
+        let mut last: Option<(usize, char)> = None;
+
+        for found in self.automaton.find_overlapping_iter(line) {
+            let found = (found.start(), self.values[found.pattern().as_usize()]);
+            if first.is_none_or(|(start, _)| found.0 < start) {
+                first = Some(found);
+            }
+            if last.is_none_or(|(start, _)| found.0 > start) {
+                last = Some(found);
             }
         }
 
-        if let Some((a, _)) = first {
-            if let Some((b, _)) = last {
-                let value = format!("{}{}", a, b).parse::<u32>()?;
-                values.push(value);
+        Some(Matches {
+            first: first?.1,
+            last: last?.1,
+        })
+    }
+}
+
+/// The outcome of calibrating one line, numbered from one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Calibration {
+    Value {
+        line: usize,
+        value: u32,
+    },
+    /// The line holds no token, so no value can be recovered from it.
+    Missing {
+        line: usize,
+    },
+}
+
+/// Calibrates lines as they are read, holding only the current line in memory.
+pub struct Calibrations<R> {
+    reader: R,
+    scanner: Scanner,
+    buffer: String,
+    line: usize,
+    /// Set once the reader fails in a way that skipping the line can't recover from.
+    finished: bool,
+}
+
+impl<R: BufRead> Iterator for Calibrations<R> {
+    type Item = Result<Calibration>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        self.buffer.clear();
+        let read = self.reader.read_line(&mut self.buffer);
+        // A line that fails to read is still a line, so later lines keep their numbers.
+        self.line += 1;
+        let line = self.line;
+        match read {
+            Ok(0) => None,
+            Ok(_) => {
+                let text = self.buffer.trim_end_matches(['\n', '\r']);
+                Some(match self.scanner.scan(text) {
+                    Some(Matches { first, last }) => format!("{}{}", first, last)
+                        .parse::<u32>()
+                        .map(|value| Calibration::Value { line, value })
+                        .with_context(|| format!("invalid calibration value on line {}", line)),
+                    None => Ok(Calibration::Missing { line }),
+                })
+            }
+            Err(error) => {
+                // Only invalid UTF-8 consumes the line; any other error would just repeat.
+                self.finished = error.kind() != ErrorKind::InvalidData;
+                Some(Err(error).with_context(|| format!("couldn't read line {}", line)))
             }
         }
+    }
+}
+
+pub fn calibrations<R: BufRead>(
+    reader: R,
+    tokens: &HashMap<&str, char>,
+) -> Result<Calibrations<R>> {
+    Ok(Calibrations {
+        reader,
+        scanner: Scanner::new(tokens)?,
+        buffer: String::new(),
+        line: 0,
+        finished: false,
+    })
+}
+
+pub fn calibrate(input: &str, tokens: &HashMap<&str, char>) -> Result<Vec<u32>> {
+    calibrations(input.as_bytes(), tokens)?
+        .filter_map(|calibration| match calibration {
+            Ok(Calibration::Value { value, .. }) => Some(Ok(value)),
+            Ok(Calibration::Missing { .. }) => None,
+            Err(error) => Some(Err(error)),
+        })
+        .collect()
+}
 
-        first = None;
-        last = None;
+const DIGITS: [(&str, char); 9] = [
+    ("1", '1'),
+    ("2", '2'),
+    ("3", '3'),
+    ("4", '4'),
+    ("5", '5'),
+    ("6", '6'),
+    ("7", '7'),
+    ("8", '8'),
+    ("9", '9'),
+];
+
+const WORDS: [(&str, char); 9] = [
+    ("one", '1'),
+    ("two", '2'),
+    ("three", '3'),
+    ("four", '4'),
+    ("five", '5'),
+    ("six", '6'),
+    ("seven", '7'),
+    ("eight", '8'),
+    ("nine", '9'),
+];
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = String;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Ok(input.to_string())
     }
 
-    Ok(values)
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        let tokens = HashMap::from(DIGITS);
+        Ok(calibrate(input, &tokens)?.iter().sum::<u32>().into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        let tokens = HashMap::from_iter(DIGITS.into_iter().chain(WORDS));
+        Ok(calibrate(input, &tokens)?.iter().sum::<u32>().into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
+    use std::io;
 
     #[test]
     fn part_1() -> Result<()> {
-        let input = include_str!("../res/01.txt");
+        let input = load(1, Variant::Actual)?;
         let tokens: HashMap<&str, char> = HashMap::from([
             ("1", '1'),
             ("2", '2'),
@@ -67,14 +196,14 @@ mod tests {
             ("9", '9'),
         ]);
 
-        let actual = calibrate(input, &tokens);
+        let actual = calibrate(&input, &tokens);
         assert_eq!(actual?.iter().sum::<u32>(), 54632);
         Ok(())
     }
 
     #[test]
     fn part_2() -> Result<()> {
-        let input = include_str!("../res/01.txt");
+        let input = load(1, Variant::Actual)?;
         let tokens: HashMap<&str, char> = HashMap::from([
             ("one", '1'),
             ("two", '2'),
@@ -96,8 +225,81 @@ mod tests {
             ("9", '9'),
         ]);
 
-        let actual = calibrate(input, &tokens);
+        let actual = calibrate(&input, &tokens);
         assert_eq!(actual?.iter().sum::<u32>(), 54019);
         Ok(())
     }
+
+    #[test]
+    fn overlapping() -> Result<()> {
+        let scanner = Scanner::new(&HashMap::from_iter(DIGITS.into_iter().chain(WORDS)))?;
+        let matches = |first, last| Some(Matches { first, last });
+        assert_eq!(scanner.scan("oneight"), matches('1', '8'));
+        assert_eq!(scanner.scan("xtwone3four"), matches('2', '4'));
+        assert_eq!(scanner.scan("7"), matches('7', '7'));
+        assert_eq!(scanner.scan("eighthree"), matches('8', '3'));
+        assert_eq!(scanner.scan("abc"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn arbitrary_tokens() -> Result<()> {
+        let tokens = HashMap::from([("zero", '0'), ("ten", '9'), ("tenth", '5')]);
+        assert_eq!(
+            calibrate("tenth zero\nzeroten\nnothing\n", &tokens)?,
+            vec![90, 9]
+        );
+        assert!(Scanner::new(&HashMap::from([("", '1')])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn streaming() -> Result<()> {
+        let reader = io::BufReader::new("1abc2\r\n\npqr3stu8vwx\nnone\n".as_bytes());
+        let results = calibrations(reader, &HashMap::from(DIGITS))?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            results,
+            vec![
+                Calibration::Value { line: 1, value: 12 },
+                Calibration::Missing { line: 2 },
+                Calibration::Value { line: 3, value: 38 },
+                Calibration::Missing { line: 4 },
+            ]
+        );
+
+        let reader = io::BufReader::new(&b"1a\n\xff2\n3b\n"[..]);
+        let results = calibrations(reader, &HashMap::from(DIGITS))?.collect::<Vec<_>>();
+        assert_eq!(results.len(), 3);
+        assert!(results[1].is_err());
+        assert_eq!(
+            results[1].as_ref().unwrap_err().to_string(),
+            "couldn't read line 2"
+        );
+        assert_eq!(
+            results[2].as_ref().unwrap(),
+            &Calibration::Value { line: 3, value: 33 }
+        );
+        Ok(())
+    }
+
+    /// A reader whose every read fails, like a device returning EIO.
+    struct Broken;
+
+    impl io::Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("device failed"))
+        }
+    }
+
+    #[test]
+    fn failing_reader() -> Result<()> {
+        let reader = io::BufReader::new(Broken);
+        let results = calibrations(reader, &HashMap::from(DIGITS))?.collect::<Vec<_>>();
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].as_ref().unwrap_err().to_string(),
+            "couldn't read line 1"
+        );
+        Ok(())
+    }
 }
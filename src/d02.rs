@@ -1,109 +1,367 @@
-use anyhow::{anyhow, Context, Result};
-use std::collections::HashMap;
+use crate::error::{self, Line, Mode, ParseError};
+use crate::{Answer, Solution};
+use anyhow::{Context, Result};
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
 
-#[derive(Debug)]
+const DAY: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Colour {
+    Red,
+    Green,
+    Blue,
+}
+
+impl Colour {
+    pub const ALL: [Colour; 3] = [Colour::Red, Colour::Green, Colour::Blue];
+}
+
+impl fmt::Display for Colour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Colour::Red => write!(f, "red"),
+            Colour::Green => write!(f, "green"),
+            Colour::Blue => write!(f, "blue"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Colour {
+    type Error = ();
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value {
+            "red" => Ok(Colour::Red),
+            "green" => Ok(Colour::Green),
+            "blue" => Ok(Colour::Blue),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Cube counts by colour, used both for a revealed sample and for the contents of a bag.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bag {
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+}
+
+impl Bag {
+    pub fn new(red: u32, green: u32, blue: u32) -> Self {
+        Bag { red, green, blue }
+    }
+
+    pub fn get(&self, colour: Colour) -> u32 {
+        match colour {
+            Colour::Red => self.red,
+            Colour::Green => self.green,
+            Colour::Blue => self.blue,
+        }
+    }
+
+    fn get_mut(&mut self, colour: Colour) -> &mut u32 {
+        match colour {
+            Colour::Red => &mut self.red,
+            Colour::Green => &mut self.green,
+            Colour::Blue => &mut self.blue,
+        }
+    }
+
+    /// Whether `sample` could have been drawn from this bag.
+    pub fn holds(&self, sample: &Bag) -> bool {
+        Colour::ALL
+            .iter()
+            .all(|&colour| sample.get(colour) <= self.get(colour))
+    }
+
+    /// The smallest bag holding both `self` and `other`.
+    pub fn union(&self, other: &Bag) -> Bag {
+        Bag {
+            red: self.red.max(other.red),
+            green: self.green.max(other.green),
+            blue: self.blue.max(other.blue),
+        }
+    }
+
+    pub fn power(&self) -> u128 {
+        u128::from(self.red) * u128::from(self.green) * u128::from(self.blue)
+    }
+
+    pub fn total(&self) -> u64 {
+        u64::from(self.red) + u64::from(self.green) + u64::from(self.blue)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Game {
     id: u32,
-    samples: Vec<HashMap<String, u32>>,
+    samples: Vec<Bag>,
 }
 
 impl Game {
-    fn try_new(line: &str) -> Result<Self> {
+    fn try_new(line: Line, mode: Mode) -> Result<Self, ParseError> {
         let (id, samples) = line
+            .text()
             .split_once(':')
-            .with_context(|| format!("parsing error on line: {:?}", line))?;
+            .ok_or_else(|| line.malformed(line.text(), "\"Game <id>: <samples>\""))?;
         Ok(Self {
-            id: parse_id(id)?,
-            samples: parse_samples(samples)?,
+            id: parse_id(&line, id)?,
+            samples: parse_samples(&line, samples, mode)?,
         })
     }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn samples(&self) -> &[Bag] {
+        &self.samples
+    }
+
+    /// The most cubes of `colour` revealed in any one sample.
+    pub fn max(&self, colour: Colour) -> u32 {
+        self.samples
+            .iter()
+            .map(|sample| sample.get(colour))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The fewest cubes of each colour that make this game possible.
+    pub fn minimal(&self) -> Bag {
+        self.samples
+            .iter()
+            .fold(Bag::default(), |bag, sample| bag.union(sample))
+    }
+
+    pub fn is_possible(&self, bag: &Bag) -> bool {
+        self.samples.iter().all(|sample| bag.holds(sample))
+    }
 }
 
-pub fn parse_games(input: &str) -> Result<Vec<Game>> {
-    input
-        .split_inclusive('\n')
-        .map(|l| Game::try_new(l))
-        .collect()
+/// Renders the non-zero counts in red, green, blue order, e.g. `4 red, 3 blue`.
+/// Leaves out zero counts, except that an empty bag still names one colour so it parses back.
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.total() == 0 {
+            return write!(f, "0 {}", Colour::Red);
+        }
+
+        let mut separator = "";
+        for colour in Colour::ALL.into_iter().filter(|&c| self.get(c) > 0) {
+            write!(f, "{}{} {}", separator, self.get(colour), colour)?;
+            separator = ", ";
+        }
+        Ok(())
+    }
 }
 
-fn parse_samples(text: &str) -> Result<Vec<HashMap<String, u32>>> {
-    text.split(';').map(|s| parse_sample(s)).collect()
+/// Renders the puzzle line the game parses from.
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Game {}:", self.id)?;
+        for (i, sample) in self.samples.iter().enumerate() {
+            write!(f, "{} {}", if i == 0 { "" } else { ";" }, sample)?;
+        }
+        Ok(())
+    }
 }
 
-fn parse_sample(text: &str) -> Result<HashMap<String, u32>> {
-    text.split(',')
-        .filter_map(|x| x.trim().split_once(' '))
-        .map(|x| {
-            let (a, b) = x;
-            if let Ok(c) = a.parse::<u32>() {
-                Ok((b.to_string(), c))
-            } else {
-                Err(anyhow!(format!("parsing error on line: {:?}", text)))
-            }
-        })
+/// Renders one puzzle line per game, the inverse of [`parse_games`].
+pub fn to_text(games: &[Game]) -> String {
+    games.iter().map(|game| format!("{}\n", game)).collect()
+}
+
+pub fn to_json(games: &[Game]) -> Result<String> {
+    serde_json::to_string_pretty(games).context("couldn't serialize games")
+}
+
+pub fn from_json(json: &str) -> Result<Vec<Game>> {
+    serde_json::from_str(json).context("couldn't deserialize games")
+}
+
+pub fn parse_games(input: &str, mode: Mode) -> Result<Vec<Game>> {
+    Ok(error::lines(DAY, input)
+        .map(|line| Game::try_new(line, mode))
+        .collect::<Result<_, _>>()?)
+}
+
+fn parse_samples(line: &Line, text: &str, mode: Mode) -> Result<Vec<Bag>, ParseError> {
+    if text.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    text.split(';')
+        .map(|sample| parse_sample(line, sample, mode))
         .collect()
 }
 
-fn parse_id(text: &str) -> Result<u32> {
-    text["Game ".len()..]
-        .parse::<u32>()
-        .with_context(|| format!("couldn't find game id in {}", text))
+fn parse_sample(line: &Line, text: &str, mode: Mode) -> Result<Bag, ParseError> {
+    let mut bag = Bag::default();
+    let mut seen = HashSet::new();
+
+    for x in text.split(',') {
+        let Some((a, b)) = x.trim().split_once(' ') else {
+            match mode {
+                Mode::Strict => return Err(line.malformed(x, "\"<count> <colour>\"")),
+                Mode::Lenient => continue,
+            }
+        };
+        let colour = match (Colour::try_from(b), mode) {
+            (Ok(colour), _) => colour,
+            (Err(_), Mode::Strict) => return Err(line.malformed(b, "red, green or blue")),
+            (Err(_), Mode::Lenient) => continue,
+        };
+        if !seen.insert(colour) {
+            match mode {
+                Mode::Strict => return Err(line.malformed(b, "each colour once per sample")),
+                Mode::Lenient => continue,
+            }
+        }
+        *bag.get_mut(colour) = a
+            .parse::<u32>()
+            .map_err(|_| line.malformed(a, "a cube count"))?;
+    }
+
+    Ok(bag)
 }
 
-pub fn sum_power(games: Vec<Game>) -> u32 {
-    games
-        .iter()
-        .map(|g| {
-            let mut power: HashMap<&String, u32> = HashMap::new();
-            g.samples.iter().for_each(|s| {
-                s.iter().for_each(|(k, v)| {
-                    power
-                        .entry(k)
-                        .and_modify(|e| {
-                            if *e < *v {
-                                *e = *v
-                            }
-                        })
-                        .or_insert(*v);
-                });
-            });
-
-            power.iter().fold(1, |c, (_, v)| v * c)
-        })
-        .sum()
+fn parse_id(line: &Line, text: &str) -> Result<u32, ParseError> {
+    text.strip_prefix("Game ")
+        .and_then(|id| id.parse::<u32>().ok())
+        .ok_or_else(|| line.malformed(text, "\"Game <id>\""))
 }
 
-pub fn check_validity(games: Vec<Game>, constraints: &HashMap<&str, u32>) -> u32 {
+/// The games that could have been played with `bag`.
+pub fn possible<'a>(games: &'a [Game], bag: &'a Bag) -> impl Iterator<Item = &'a Game> {
+    games.iter().filter(move |game| game.is_possible(bag))
+}
+
+/// The smallest single bag with which every game is possible.
+pub fn fewest(games: &[Game]) -> Bag {
     games
         .iter()
-        .filter(|game| {
-            !constraints.iter().any(|(k, v)| {
-                game.samples
-                    .iter()
-                    .any(|sample| sample.get(k as &str).is_some_and(|y| y > v))
-            })
-        })
-        .map(|game| game.id)
-        .sum::<u32>()
+        .fold(Bag::default(), |bag, game| bag.union(&game.minimal()))
+}
+
+pub fn sum_power(games: &[Game]) -> u128 {
+    games.iter().map(|game| game.minimal().power()).sum()
+}
+
+pub fn check_validity(games: &[Game], bag: &Bag) -> u32 {
+    possible(games, bag).map(|game| game.id).sum()
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Vec<Game>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_games(input, Mode::Strict)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(check_validity(input, &Bag::new(12, 13, 14)).into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(BigInt::from(sum_power(input)).into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
 
     #[test]
     fn part_1() -> Result<()> {
-        let constraints: HashMap<&str, u32> =
-            HashMap::from([("red", 12), ("green", 13), ("blue", 14)]);
-        let input = include_str!("../res/02.txt");
-        assert_eq!(check_validity(parse_games(input)?, &constraints), 1734);
+        let input = load(2, Variant::Actual)?;
+        assert_eq!(
+            check_validity(&parse_games(&input, Mode::Strict)?, &Bag::new(12, 13, 14)),
+            1734
+        );
         Ok(())
     }
 
     #[test]
     fn part_2() -> Result<()> {
-        let input = include_str!("../res/02.txt");
-        assert_eq!(sum_power(parse_games(input)?), 70387);
+        let input = load(2, Variant::Actual)?;
+        assert_eq!(sum_power(&parse_games(&input, Mode::Strict)?), 70387);
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() {
+        let input = "Game 1: 3 blue, red\nGame 2: 4 red, x green\n";
+        let error = parse_games(input, Mode::Strict).unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(
+            (error.day(), error.line(), error.column()),
+            (Some(2), 1, Some(17))
+        );
+        assert_eq!(error.snippet(), Some("red"));
+
+        let error = parse_games(input, Mode::Lenient).unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!((error.line(), error.column()), (2, Some(16)));
+        assert_eq!(error.snippet(), Some("x"));
+
+        let input = "Game 1: 4294967295 red, 1 red\n";
+        let error = parse_games(input, Mode::Strict).unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!((error.column(), error.snippet()), (Some(27), Some("red")));
+        let games = parse_games(input, Mode::Lenient).unwrap();
+        assert_eq!(games[0].minimal(), Bag::new(4294967295, 0, 0));
+
+        let bag = Bag::new(u32::MAX, u32::MAX, u32::MAX);
+        assert_eq!(bag.power(), u128::from(u32::MAX).pow(3));
+        assert_eq!(bag.total(), 3 * u64::from(u32::MAX));
+    }
+
+    #[test]
+    fn queries() -> Result<()> {
+        let input = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green\n\
+                     Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue\n\
+                     Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red\n";
+        let games = parse_games(input, Mode::Strict)?;
+        assert_eq!(games[0].id(), 1);
+        assert_eq!(games[0].max(Colour::Blue), 6);
+        assert_eq!(games[0].minimal(), Bag::new(4, 2, 6));
+        assert_eq!(games[0].minimal().power(), 48);
+
+        let bag = Bag::new(12, 13, 14);
+        let ids: Vec<u32> = possible(&games, &bag).map(Game::id).collect();
+        assert_eq!(ids, [1, 2]);
+        assert_eq!(fewest(&games), Bag::new(20, 13, 6));
+        assert_eq!(fewest(&games).total(), 39);
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let input = load(2, Variant::Actual)?;
+        let games = parse_games(&input, Mode::Strict)?;
+        assert_eq!(parse_games(&to_text(&games), Mode::Strict)?, games);
+        assert_eq!(from_json(&to_json(&games)?)?, games);
+
+        let games = parse_games("Game 7: 3 blue, 4 red; 2 green\n", Mode::Strict)?;
+        assert_eq!(to_text(&games), "Game 7: 4 red, 3 blue; 2 green\n");
+        let json = r#"[{"id": 7, "samples": [{"red": 4, "blue": 3}, {"green": 2}]}]"#;
+        assert_eq!(from_json(json)?, games);
+        assert!(from_json(r#"[{"id": 7}]"#).is_err());
+
+        let games = parse_games("Game 1: 0 red; 2 blue\n", Mode::Strict)?;
+        assert_eq!(to_text(&games), "Game 1: 0 red; 2 blue\n");
+        let games = from_json(r#"[{"id": 3, "samples": [{}]}, {"id": 4, "samples": []}]"#)?;
+        assert_eq!(to_text(&games), "Game 3: 0 red\nGame 4:\n");
+        assert_eq!(parse_games(&to_text(&games), Mode::Strict)?, games);
         Ok(())
     }
 }
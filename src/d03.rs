@@ -1,97 +1,241 @@
-use anyhow::{bail, Context, Error, Result};
-use itertools::Itertools;
+use crate::error::{self, ParseError};
+use crate::grid::Grid;
+use crate::{Answer, Solution};
+use anyhow::{Context, Result};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
+const DAY: u8 = 3;
 const SPACER: char = '.';
 const GEAR: char = '*';
 
-#[derive(Debug)]
+/// How the characters of a schematic are classified.
+///
+/// Digits always form numbers. Characters that are neither spacers nor symbols are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub spacers: HashSet<char>,
+    /// The symbol characters, or `None` to treat every non-digit non-spacer as a symbol.
+    pub symbols: Option<HashSet<char>>,
+    pub gears: HashSet<char>,
+    /// The number of adjacent parts a gear symbol needs to count as a gear.
+    pub gear_parts: usize,
+}
+
+impl Config {
+    pub fn is_symbol(&self, character: char) -> bool {
+        !character.is_ascii_digit()
+            && !self.spacers.contains(&character)
+            && self
+                .symbols
+                .as_ref()
+                .is_none_or(|symbols| symbols.contains(&character))
+    }
+
+    pub fn is_gear(&self, marker: &Marker) -> bool {
+        self.gears.contains(&marker.symbol.character) && marker.parts.len() == self.gear_parts
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            spacers: HashSet::from([SPACER]),
+            symbols: None,
+            gears: HashSet::from([GEAR]),
+            gear_parts: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol {
+    pub row: usize,
+    pub column: usize,
+    pub character: char,
+}
+
+/// A number in the schematic with its position and the symbols around any of its digits.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Part {
     number: u32,
+    row: usize,
+    column: usize,
+    span: usize,
+    symbols: BTreeSet<Symbol>,
 }
 
-impl TryFrom<Vec<&char>> for Part {
-    type Error = anyhow::Error;
+impl Part {
+    pub fn number(&self) -> u32 {
+        self.number
+    }
 
-    fn try_from(value: Vec<&char>) -> std::result::Result<Self, self::Error> {
-        String::from_iter(value)
-            .parse::<u32>()
-            .map(|number| Part { number })
-            .with_context(|| format!("could not parse part number"))
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn span(&self) -> usize {
+        self.span
+    }
+
+    pub fn symbols(&self) -> &BTreeSet<Symbol> {
+        &self.symbols
+    }
+
+    /// Whether the number touches a symbol and so counts as a part number.
+    pub fn is_part(&self) -> bool {
+        !self.symbols.is_empty()
     }
 }
 
 #[derive(Debug)]
 pub struct Marker {
-    symbol: char,
+    symbol: Symbol,
     parts: Vec<Part>,
 }
 
-struct Schematic {
-    lines: Vec<Vec<char>>,
+impl Marker {
+    pub fn symbol(&self) -> Symbol {
+        self.symbol
+    }
+
+    /// The part numbers touching the symbol.
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+}
+
+#[derive(Debug)]
+pub struct Schematic {
+    config: Config,
+    grid: Grid<char>,
+    numbers: Vec<Part>,
+    adjacent: HashMap<(usize, usize), Vec<usize>>,
 }
 
 impl Schematic {
-    fn scan(&self, row: usize, column: usize) -> Result<Vec<Part>> {
-        [self.line(row, -1), self.line(row, 0), self.line(row, 1)]
-            .into_iter()
-            .filter_map(|line| line)
-            .map(|line| self.parts(line, column))
-            .flatten_ok()
-            .collect::<Result<Vec<Part>>>()
+    pub fn parse(value: &str, config: Config) -> Result<Self, ParseError> {
+        let grid = Grid::try_from(value).map_err(|e| e.on_day(DAY))?;
+        let numbers = Schematic::locate(value, &grid, &config)?;
+
+        let mut adjacent: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (i, part) in numbers.iter().enumerate() {
+            for symbol in &part.symbols {
+                adjacent
+                    .entry((symbol.row, symbol.column))
+                    .or_default()
+                    .push(i);
+            }
+        }
+
+        Ok(Schematic {
+            config,
+            grid,
+            numbers,
+            adjacent,
+        })
     }
 
-    fn line(&self, row: usize, offset: isize) -> Option<&Vec<char>> {
-        row.checked_add_signed(offset)
-            .map_or(None, |i| self.lines.get(i))
+    fn locate(value: &str, grid: &Grid<char>, config: &Config) -> Result<Vec<Part>, ParseError> {
+        let mut numbers = vec![];
+
+        for ((row, line), text) in grid.rows().enumerate().zip(error::lines(DAY, value)) {
+            let mut column = 0;
+            while column < line.len() {
+                let span = line[column..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .count();
+                if span == 0 {
+                    column += 1;
+                    continue;
+                }
+
+                let number = String::from_iter(&line[column..column + span])
+                    .parse::<u32>()
+                    .map_err(|_| {
+                        // Digits are ASCII, so the span is as many bytes as characters.
+                        let offset = text.text().char_indices().nth(column).map_or(0, |(i, _)| i);
+                        text.malformed_at(offset, span, "a part number")
+                    })?;
+                let symbols = (column..column + span)
+                    .flat_map(|c| grid.surrounding((row, c)))
+                    .filter(|(_, &character)| config.is_symbol(character))
+                    .map(|((row, column), &character)| Symbol {
+                        row,
+                        column,
+                        character,
+                    })
+                    .collect();
+
+                numbers.push(Part {
+                    number,
+                    row,
+                    column,
+                    span,
+                    symbols,
+                });
+                column += span;
+            }
+        }
+
+        Ok(numbers)
     }
 
-    fn parts(&self, row: &Vec<char>, offset: usize) -> Result<Vec<Part>> {
-        let mut left: Vec<&char> = row
-            .iter()
-            .rev()
-            .skip(row.len() - offset)
-            .take_while(|e| e.is_digit(10))
-            .collect();
-        left.reverse();
+    /// Every number in the schematic, whether or not it touches a symbol.
+    pub fn numbers(&self) -> &[Part] {
+        &self.numbers
+    }
 
-        let right: Vec<&char> = row
-            .iter()
-            .skip(offset + 1)
-            .take_while(|e| e.is_digit(10))
-            .collect();
+    /// Each part number once, however many symbols or cells it touches them from.
+    pub fn parts(&self) -> impl Iterator<Item = &Part> {
+        self.numbers.iter().filter(|part| part.is_part())
+    }
 
-        row.get(offset).map_or_else(
-            || bail!("expected character"),
-            |character| {
-                if character.is_digit(10) {
-                    left.push(character);
-                    left.extend(right);
-                    Part::try_from(left).map(|part| vec![part])
-                } else {
-                    [Part::try_from(left), Part::try_from(right)]
-                        .into_iter()
-                        .filter(|e| e.is_ok())
-                        .collect()
-                }
-            },
-        )
+    /// The products of the parts around each gear, failing for any that overflows a `u64`.
+    pub fn ratios(&self) -> impl Iterator<Item = Result<u64>> + '_ {
+        self.into_iter()
+            .filter(|marker| self.config.is_gear(marker))
+            .map(|marker| {
+                marker
+                    .parts
+                    .iter()
+                    .try_fold(1u64, |ratio, part| ratio.checked_mul(part.number.into()))
+                    .with_context(|| {
+                        format!(
+                            "gear ratio at line {}, column {} overflows",
+                            marker.symbol.row + 1,
+                            marker.symbol.column + 1
+                        )
+                    })
+            })
+    }
+
+    fn marker(&self, symbol: Symbol) -> Marker {
+        let parts = self
+            .adjacent
+            .get(&(symbol.row, symbol.column))
+            .map_or_else(Vec::new, |indices| {
+                indices.iter().map(|&i| self.numbers[i].clone()).collect()
+            });
+        Marker { symbol, parts }
     }
 }
 
-impl From<&str> for Schematic {
-    fn from(value: &str) -> Self {
-        Schematic {
-            lines: value
-                .split('\n')
-                .map(|line| line.chars().collect())
-                .collect(),
-        }
+impl TryFrom<&str> for Schematic {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        Schematic::parse(value, Config::default())
     }
 }
 
-impl IntoIterator for Schematic {
-    type Item = Result<Marker>;
-    type IntoIter = SchematicIntoIterator;
+impl<'a> IntoIterator for &'a Schematic {
+    type Item = Marker;
+    type IntoIter = SchematicIntoIterator<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
         SchematicIntoIterator {
@@ -102,90 +246,190 @@ impl IntoIterator for Schematic {
     }
 }
 
-pub struct SchematicIntoIterator {
-    schematic: Schematic,
+pub struct SchematicIntoIterator<'a> {
+    schematic: &'a Schematic,
     row: usize,
     column: usize,
 }
 
-impl Iterator for SchematicIntoIterator {
-    type Item = Result<Marker>;
+impl Iterator for SchematicIntoIterator<'_> {
+    type Item = Marker;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let row = self.row;
-        let column = self.column;
-
-        match self.schematic.lines.get(row) {
-            Some(line) => match line.get(column) {
-                Some(&symbol) => {
-                    self.column = self.column + 1;
-                    if !symbol.is_digit(10) && symbol != SPACER {
-                        Some(
-                            self.schematic
-                                .scan(row, column)
-                                .map(|parts| Marker { symbol, parts }),
-                        )
-                    } else {
-                        self.next()
-                    }
-                }
-                None => {
-                    self.row = self.row + 1;
-                    self.column = 0;
-                    self.next()
+        while let Some(line) = self.schematic.grid.row(self.row) {
+            while let Some(&character) = line.get(self.column) {
+                let column = self.column;
+                self.column += 1;
+                if self.schematic.config.is_symbol(character) {
+                    return Some(self.schematic.marker(Symbol {
+                        row: self.row,
+                        column,
+                        character,
+                    }));
                 }
-            },
-            None => None,
+            }
+
+            self.row += 1;
+            self.column = 0;
         }
+
+        None
     }
 }
 
 pub fn part_one(input: &str) -> Result<Vec<u32>> {
-    Schematic::from(input)
-        .into_iter()
-        .map(|result| result.map(|marker| marker.parts))
-        .flatten_ok()
-        .map(|result| result.map(|part| part.number))
-        .collect()
+    Ok(Schematic::try_from(input)?
+        .parts()
+        .map(|part| part.number)
+        .collect())
+}
+
+pub fn part_two(input: &str) -> Result<Vec<u64>> {
+    Schematic::try_from(input)?.ratios().collect()
 }
 
-pub fn part_two(input: &str) -> Result<Vec<u32>> {
-    Schematic::from(input)
-        .into_iter()
-        .filter_ok(|marker| marker.symbol.eq(&GEAR) && marker.parts.len() == 2)
-        .map(|result| result.map(|marker| marker.parts.iter().map(|parts| parts.number).product()))
-        .collect()
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Schematic;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Ok(Schematic::try_from(input)?)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(input
+            .parts()
+            .map(|part| u64::from(part.number))
+            .sum::<u64>()
+            .into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        let total = input.ratios().try_fold(0u64, |total, ratio| {
+            total.checked_add(ratio?).context("gear ratios overflow")
+        })?;
+        Ok(total.into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
 
     #[test]
     fn example_1() -> Result<()> {
-        let input = include_str!("../res/03.example");
-        assert_eq!(part_one(input)?.into_iter().sum::<u32>(), 4361);
+        let input = load(3, Variant::Example(1))?;
+        assert_eq!(part_one(&input)?.into_iter().sum::<u32>(), 4361);
         Ok(())
     }
 
     #[test]
     fn solution_1() -> Result<()> {
-        let input = include_str!("../res/03.actual");
-        assert_eq!(part_one(input)?.into_iter().sum::<u32>(), 538_046);
+        let input = load(3, Variant::Actual)?;
+        assert_eq!(part_one(&input)?.into_iter().sum::<u32>(), 538_046);
         Ok(())
     }
 
     #[test]
     fn example_2() -> Result<()> {
-        let input = include_str!("../res/03.example");
-        assert_eq!(part_two(input)?.into_iter().sum::<u32>(), 467_835);
+        let input = load(3, Variant::Example(1))?;
+        assert_eq!(part_two(&input)?.into_iter().sum::<u64>(), 467_835);
         Ok(())
     }
 
     #[test]
     fn solution_2() -> Result<()> {
-        let input = include_str!("../res/03.actual");
-        assert_eq!(part_two(input)?.into_iter().sum::<u32>(), 81_709_807);
+        let input = load(3, Variant::Actual)?;
+        assert_eq!(part_two(&input)?.into_iter().sum::<u64>(), 81_709_807);
         Ok(())
     }
+
+    #[test]
+    fn positions() -> Result<()> {
+        let schematic = Schematic::try_from("*12*\n.*..\n....\n..7.\n")?;
+        let numbers = schematic.numbers();
+        assert_eq!(numbers.len(), 2);
+        assert_eq!(
+            (numbers[0].row(), numbers[0].column(), numbers[0].span()),
+            (0, 1, 2)
+        );
+        let symbols: Vec<(usize, usize)> = numbers[0]
+            .symbols()
+            .iter()
+            .map(|s| (s.row, s.column))
+            .collect();
+        assert_eq!(symbols, [(0, 0), (0, 3), (1, 1)]);
+        assert!(!numbers[1].is_part());
+        assert_eq!(part_one("*12*\n.*..\n")?, [12]);
+        Ok(())
+    }
+
+    #[test]
+    fn configured() -> Result<()> {
+        let input = "2.3#4\n-x+..\n5....\n";
+        let sum = |config: Config| -> Result<u32> {
+            Ok(Schematic::parse(input, config)?
+                .parts()
+                .map(Part::number)
+                .sum())
+        };
+        assert_eq!(sum(Config::default())?, 14);
+
+        let config = Config {
+            spacers: HashSet::from(['.', '-']),
+            symbols: Some(HashSet::from(['x', '+'])),
+            gears: HashSet::from(['x']),
+            gear_parts: 3,
+        };
+        assert_eq!(sum(config.clone())?, 10);
+        let ratios: Vec<u64> = Schematic::parse(input, config.clone())?
+            .ratios()
+            .collect::<Result<_>>()?;
+        assert_eq!(ratios, [30]);
+
+        let input = "99999.99998\n.....x.....\n....99997..\n";
+        let ratios: Vec<u64> = Schematic::parse(input, config.clone())?
+            .ratios()
+            .collect::<Result<_>>()?;
+        assert_eq!(ratios, [99_999 * 99_998 * 99_997]);
+
+        let input = "4294967295.4294967295\n..........x..........\n.........4294967295..\n";
+        let error = Schematic::parse(input, config)?.ratios().next().unwrap();
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "gear ratio at line 2, column 11 overflows"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn large() -> Result<()> {
+        let mut input = "12..34....".repeat(100) + "\n";
+        input = input.repeat(999) + &".".repeat(999) + "*\n";
+        let markers: Vec<Marker> = Schematic::try_from(input.as_str())?.into_iter().collect();
+        assert_eq!(markers.len(), 1);
+        assert_eq!(
+            (markers[0].symbol().row, markers[0].symbol().column),
+            (999, 999)
+        );
+        assert_eq!(markers[0].parts().len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() {
+        let error = Schematic::try_from("..1\né*99999999999\n").unwrap_err();
+        assert_eq!((error.line(), error.column()), (2, Some(1)));
+        let error = Schematic::try_from("é*99999999999\n").unwrap_err();
+        assert_eq!(
+            (error.day(), error.line(), error.column()),
+            (Some(3), 1, Some(3))
+        );
+        assert_eq!(error.snippet(), Some("99999999999"));
+
+        let error = Schematic::try_from("1.\n...\n").unwrap_err();
+        assert_eq!((error.day(), error.line()), (Some(3), 2));
+    }
 }
@@ -1,6 +1,12 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
-use anyhow::{bail, Context, Result};
+use crate::error::{self, Line, Mode, ParseError};
+use crate::{Answer, Solution};
+use anyhow::{Context, Result};
+use itertools::Itertools;
+
+const DAY: u8 = 4;
 
 #[derive(Debug)]
 pub struct Card {
@@ -16,104 +22,333 @@ impl Card {
 
     fn points(&self) -> u32 {
         let winners: Vec<&u32> = self.matches();
-        if winners.len() > 0 {
-            1 << winners.len() - 1
+        if !winners.is_empty() {
+            1 << (winners.len() - 1)
         } else {
             0
         }
     }
+}
+
+/// What one card contributed to the cascade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub id: u32,
+    /// The original plus every copy won from earlier cards.
+    pub copies: u64,
+    pub matches: usize,
+    /// The later cards each copy won, leaving out ids past the last card.
+    pub spawned: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cascade {
+    steps: Vec<Step>,
+    total: u64,
+}
+
+impl Cascade {
+    /// Plays out the copies each card wins, failing once a count overflows a `u64`.
+    pub fn simulate(cards: &[Card]) -> Result<Self> {
+        let index: HashMap<u32, usize> = cards
+            .iter()
+            .enumerate()
+            .map(|(i, card)| (card.id, i))
+            .collect();
+        let mut copies = vec![1u64; cards.len()];
+        let mut steps = Vec::with_capacity(cards.len());
+        let mut total = 0u64;
+
+        for (i, card) in cards.iter().enumerate() {
+            let matches = card.matches().len();
+            let spawned: Vec<u32> = match card.id.checked_add(1) {
+                Some(next) => (next..=u32::MAX)
+                    .take(matches)
+                    .filter(|id| index.contains_key(id))
+                    .collect(),
+                None => vec![],
+            };
+
+            for id in &spawned {
+                let j = index[id];
+                copies[j] = copies[j]
+                    .checked_add(copies[i])
+                    .with_context(|| format!("copies of card {} overflow", id))?;
+            }
+            total = total
+                .checked_add(copies[i])
+                .context("the total number of cards overflows")?;
+
+            steps.push(Step {
+                id: card.id,
+                copies: copies[i],
+                matches,
+                spawned,
+            });
+        }
+
+        Ok(Cascade { steps, total })
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
 
-    fn cards(&self, tally: &mut HashMap<u32, u32>) -> u32 {
-        let total = self.matches().len() as u32;
-        let cards = *tally
-            .entry(self.id)
-            .and_modify(|e| *e = *e + 1)
-            .or_insert(1);
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
 
-        for i in self.id + 1..=self.id + total {
-            tally
-                .entry(i)
-                .and_modify(|e| *e = *e + cards)
-                .or_insert(cards);
+/// Renders one row per card followed by the total.
+impl fmt::Display for Cascade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>6} {:>10} {:>7}  spawned",
+            "card", "copies", "matches"
+        )?;
+        for step in &self.steps {
+            let spawned = match (step.spawned.first(), step.spawned.last()) {
+                (Some(first), Some(last)) if step.spawned.len() > 2 => {
+                    format!("{}..={}", first, last)
+                }
+                _ => step.spawned.iter().join(", "),
+            };
+            writeln!(
+                f,
+                "{:>6} {:>10} {:>7}  {}",
+                step.id, step.copies, step.matches, spawned
+            )?;
         }
-        cards
+        writeln!(f, "{:>6} {:>10}", "total", self.total())
     }
 }
 
-fn parse_numbers(data: &str) -> Result<HashSet<u32>> {
+/// Cards read from the input along with the invariants they break.
+#[derive(Debug)]
+pub struct Validated {
+    pub cards: Vec<Card>,
+    pub warnings: Vec<ParseError>,
+}
+
+fn parse_numbers<'a>(line: &Line, data: &'a str) -> Result<Vec<(&'a str, u32)>, ParseError> {
     data.split(' ')
-        .into_iter()
         .map(|number| number.trim())
         .filter(|number| !number.is_empty())
         .map(|number| {
             number
                 .parse::<u32>()
-                .with_context(|| format!("couldn't parse number {:?}", number))
+                .map(|value| (number, value))
+                .map_err(|_| line.malformed(number, "a number"))
         })
         .collect()
 }
 
+/// Collects `numbers` into a set, noting any number seen twice.
+fn unique(line: &Line, numbers: &[(&str, u32)], warnings: &mut Vec<ParseError>) -> HashSet<u32> {
+    let mut set = HashSet::new();
+    for &(text, number) in numbers {
+        if !set.insert(number) {
+            warnings.push(line.malformed(text, "a number not already on this side of the card"));
+        }
+    }
+    set
+}
+
+/// Parses every card, checking that ids run in sequence without repeats, that no side repeats a
+/// number and that every card has as many numbers on each side as the first.
+///
+/// In [`Mode::Strict`] the first broken invariant is returned as an error; otherwise it's kept
+/// as a warning alongside the cards.
+pub fn validate_cards(data: &str, mode: Mode) -> Result<Validated> {
+    let mut cards = vec![];
+    let mut warnings = vec![];
+    let mut ids = HashSet::new();
+    let mut columns = None;
+
+    for line in error::lines(DAY, data).filter(|line| !line.text().is_empty()) {
+        let line = &line;
+        let card = line.text();
+        let (id, numbers) = card
+            .split_once(':')
+            .ok_or_else(|| line.malformed(card, "\"Card <id>: <numbers>\""))?;
+        let (wins, ours) = numbers
+            .split_once('|')
+            .ok_or_else(|| line.malformed(numbers, "\"<wins> | <ours>\""))?;
+        let number = id
+            .strip_prefix("Card")
+            .and_then(|id| id.trim().parse::<u32>().ok())
+            .ok_or_else(|| line.malformed(id, "\"Card <id>\""))?;
+
+        if !ids.insert(number) {
+            warnings.push(line.malformed(id, "a card id not already used"));
+        } else if let Some(previous) = cards.last().map(|card: &Card| card.id) {
+            if number != previous + 1 {
+                warnings.push(line.malformed(id, format!("card {}", previous + 1)));
+            }
+        }
+
+        let (wins, ours) = (parse_numbers(line, wins)?, parse_numbers(line, ours)?);
+        match columns {
+            None => columns = Some((wins.len(), ours.len())),
+            Some((w, o)) => {
+                let text = numbers.trim();
+                if wins.len() != w {
+                    warnings.push(line.malformed(text, format!("{} winning numbers", w)));
+                } else if ours.len() != o {
+                    warnings.push(line.malformed(text, format!("{} numbers of ours", o)));
+                }
+            }
+        }
+
+        cards.push(Card {
+            id: number,
+            wins: unique(line, &wins, &mut warnings),
+            ours: unique(line, &ours, &mut warnings),
+        });
+
+        if mode == Mode::Strict && !warnings.is_empty() {
+            return Err(warnings.swap_remove(0).into());
+        }
+    }
+
+    Ok(Validated { cards, warnings })
+}
+
 fn parse_cards(data: &str) -> Result<Vec<Card>> {
-    data.split('\n')
-        .filter(|card| !card.is_empty())
-        .map(|card| match card.split_once(':') {
-            Some((id, numbers)) => match numbers.split_once('|') {
-                Some((wins, ours)) => Ok(Card {
-                    id: id["Card ".len()..].trim().parse::<u32>()?,
-                    wins: parse_numbers(wins)?,
-                    ours: parse_numbers(ours)?,
-                }),
-                None => bail!("couldn't parse numbers"),
-            },
-            None => bail!("couldn't parse card"),
-        })
-        .collect()
+    Ok(validate_cards(data, Mode::Strict)?.cards)
+}
+
+fn total_points(cards: &[Card]) -> u32 {
+    cards.iter().map(Card::points).sum()
 }
 
 pub fn points(data: &str) -> Result<u32> {
-    Ok(parse_cards(data)?
-        .iter()
-        .map(|card| card.points())
-        .sum::<u32>())
+    Ok(total_points(&parse_cards(data)?))
 }
 
-pub fn cards(data: &str) -> Result<u32> {
-    let mut tally: HashMap<u32, u32> = HashMap::new();
-    Ok(parse_cards(data)?
-        .iter()
-        .map(|card| card.cards(&mut tally))
-        .sum::<u32>())
+pub fn cascade(data: &str) -> Result<Cascade> {
+    Cascade::simulate(&parse_cards(data)?)
+}
+
+pub fn cards(data: &str) -> Result<u64> {
+    Ok(cascade(data)?.total())
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Vec<Card>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_cards(input)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(total_points(input).into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(Cascade::simulate(input)?.total().into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
 
     #[test]
     fn part_1_example() -> Result<()> {
-        let input = include_str!("../res/04.example");
-        assert_eq!(points(input)?, 13);
+        let input = load(4, Variant::Example(1))?;
+        assert_eq!(points(&input)?, 13);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/04.actual");
-        assert_eq!(points(input)?, 21213);
+        let input = load(4, Variant::Actual)?;
+        assert_eq!(points(&input)?, 21213);
         Ok(())
     }
 
     #[test]
     fn part_2_example() -> Result<()> {
-        let input = include_str!("../res/04.example");
-        assert_eq!(cards(input)?, 30);
+        let input = load(4, Variant::Example(1))?;
+        assert_eq!(cards(&input)?, 30);
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/04.actual");
-        assert_eq!(cards(input)?, 8549735);
+        let input = load(4, Variant::Actual)?;
+        assert_eq!(cards(&input)?, 8549735);
+        Ok(())
+    }
+
+    #[test]
+    fn trace() -> Result<()> {
+        let input = load(4, Variant::Example(1))?;
+        let cascade = cascade(&input)?;
+        let steps = cascade.steps();
+        assert_eq!(steps.len(), 6);
+        assert_eq!(steps[0].spawned, [2, 3, 4, 5]);
+        assert_eq!(steps[3].copies, 8);
+        assert_eq!(steps[5].copies, 1);
+
+        let cascade = super::cascade("Card 1: 1 2 | 1 2\nCard 2: 3 4 | 3 5\n")?;
+        assert_eq!(cascade.steps()[1].spawned, Vec::<u32>::new());
+        assert_eq!(cascade.total(), 3);
+        assert_eq!(
+            cascade.to_string().lines().nth(1),
+            Some("     1          1       2  2")
+        );
+
+        let last = super::cascade("Card 4294967295: 1 2 | 1 2\n")?;
+        assert_eq!(last.steps()[0].spawned, Vec::<u32>::new());
+        assert_eq!(last.total(), 1);
+
+        let card = |id: usize| {
+            format!(
+                "Card {}: {} | {}\n",
+                id,
+                (1..=10).join(" "),
+                (1..=10).join(" ")
+            )
+        };
+        let input: String = (1..=40).map(card).collect();
+        let total = super::cascade(&input)?.total();
+        assert!(total > u64::from(u32::MAX));
+        let input: String = (1..=100).map(card).collect();
+        assert!(super::cascade(&input).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validation() -> Result<()> {
+        let input = "Card 1: 1 2 | 3 4\n\
+                     Card 3: 1 1 | 3 4\n\
+                     Card 3: 1 2 | 3\n";
+        let error = validate_cards(input, Mode::Strict).unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!((error.line(), error.column()), (2, Some(1)));
+
+        let validated = validate_cards(input, Mode::Lenient)?;
+        assert_eq!(validated.cards.len(), 3);
+        let warnings: Vec<(usize, Option<usize>, Option<&str>)> = validated
+            .warnings
+            .iter()
+            .map(|w| (w.line(), w.column(), w.snippet()))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                (2, Some(1), Some("Card 3")),
+                (2, Some(11), Some("1")),
+                (3, Some(1), Some("Card 3")),
+                (3, Some(9), Some("1 2 | 3")),
+            ]
+        );
         Ok(())
     }
 }
@@ -1,155 +1,725 @@
-use anyhow::{Context, Result};
+use crate::error::{self, Line, Mode, ParseError};
+use crate::{Answer, Solution};
+use anyhow::{bail, Context, Result};
 use itertools::Itertools;
+use std::fmt;
+use std::ops::Range;
 
-#[derive(Debug)]
+const DAY: u8 = 5;
+
+/// Shifts the values in `start..end` by `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Bijection {
     start: i64,
     end: i64,
     offset: i64,
 }
 
-#[derive(Debug)]
-pub struct Location {
-    result: i64,
-    supremum: i64,
-}
-
 impl Bijection {
-    fn location(&self, seed: i64) -> Option<Location> {
-        if self.start <= seed && self.end > seed {
-            Some(Location {
-                result: seed + self.offset,
-                supremum: self.end - seed,
+    pub fn new(range: Range<i64>, offset: i64) -> Self {
+        Bijection {
+            start: range.start,
+            end: range.end,
+            offset,
+        }
+    }
+
+    pub fn range(&self) -> Range<i64> {
+        self.start..self.end
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    fn parse(line: &Line) -> Result<Self, ParseError> {
+        let tokens: Vec<&str> = line.text().split_whitespace().collect();
+        let numbers = tokens
+            .iter()
+            .map(|number| {
+                number
+                    .parse::<i64>()
+                    .map_err(|_| line.malformed(number, "a number"))
             })
-        } else if self.start > seed {
-            Some(Location {
-                result: seed,
-                supremum: self.start - seed,
+            .collect::<Result<Vec<i64>, _>>()?;
+
+        let [codomain, domain, span] = numbers[..] else {
+            return Err(line.malformed(line.text(), "\"<destination> <source> <length>\""));
+        };
+        if span < 0 {
+            return Err(line.malformed(tokens[2], "a length of at least 0"));
+        }
+
+        let end = domain.checked_add(span);
+        let offset = codomain.checked_sub(domain);
+        match (end, offset) {
+            (Some(end), Some(offset)) => Ok(Bijection::new(domain..end, offset)),
+            _ => Err(line.malformed(line.text(), "ranges within i64")),
+        }
+    }
+}
+
+/// A piecewise-linear map over `i64` made of sorted, disjoint pieces.
+///
+/// Values inside a piece are shifted by its offset and every other value maps to itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalMap {
+    pieces: Vec<Bijection>,
+}
+
+impl IntervalMap {
+    /// Builds the map for one almanac layer. Where pieces overlap the one starting first wins.
+    pub fn new(mut pieces: Vec<Bijection>) -> Self {
+        pieces.sort_by_key(|piece| piece.start);
+        let mut map = IntervalMap::default();
+        for piece in pieces {
+            map.push(piece);
+        }
+        map
+    }
+
+    /// Appends a piece starting at or after the last one, merging it when it continues the last
+    /// piece with the same offset.
+    fn push(&mut self, mut piece: Bijection) {
+        if let Some(last) = self.pieces.last_mut() {
+            piece.start = piece.start.max(last.end);
+            if last.end == piece.start && last.offset == piece.offset {
+                last.end = last.end.max(piece.end);
+                return;
+            }
+        }
+        if piece.start < piece.end {
+            self.pieces.push(piece);
+        }
+    }
+
+    pub fn pieces(&self) -> &[Bijection] {
+        &self.pieces
+    }
+
+    /// The number of points where the offset can change.
+    pub fn breakpoints(&self) -> usize {
+        self.pieces.len() * 2
+    }
+
+    pub fn apply(&self, value: i64) -> i64 {
+        let i = self.pieces.partition_point(|piece| piece.end <= value);
+        match self.pieces.get(i) {
+            Some(piece) if piece.start <= value => value + piece.offset,
+            _ => value,
+        }
+    }
+
+    /// Cuts `range` where the offset changes, pairing each part with the offset applied to it.
+    fn split(&self, range: Range<i64>) -> Vec<(Range<i64>, i64)> {
+        let mut parts = vec![];
+        let mut start = range.start;
+        let first = self.pieces.partition_point(|piece| piece.end <= start);
+
+        for piece in &self.pieces[first..] {
+            if start >= range.end || piece.start >= range.end {
+                break;
+            }
+            if start < piece.start {
+                parts.push((start..piece.start, 0));
+                start = piece.start;
+            }
+            let end = piece.end.min(range.end);
+            parts.push((start..end, piece.offset));
+            start = end;
+        }
+
+        if start < range.end {
+            parts.push((start..range.end, 0));
+        }
+        parts
+    }
+
+    /// Maps every range, returning the images sorted with overlapping ones merged.
+    pub fn apply_ranges(&self, ranges: &[Range<i64>]) -> Vec<Range<i64>> {
+        coalesce(
+            ranges
+                .iter()
+                .flat_map(|range| self.split(range.clone()))
+                .map(|(range, offset)| range.start + offset..range.end + offset)
+                .collect(),
+        )
+    }
+
+    /// The pieces along with the identity gaps between them, covering every value.
+    fn segments(&self) -> Vec<Bijection> {
+        let mut segments = vec![];
+        let mut start = i64::MIN;
+        for &piece in &self.pieces {
+            if start < piece.start {
+                segments.push(Bijection {
+                    start,
+                    end: piece.start,
+                    offset: 0,
+                });
+            }
+            segments.push(piece);
+            start = piece.end;
+        }
+        if start < i64::MAX {
+            segments.push(Bijection {
+                start,
+                end: i64::MAX,
+                offset: 0,
+            });
+        }
+        segments
+    }
+
+    /// The map applying `self` and then `next`.
+    pub fn compose(&self, next: &IntervalMap) -> IntervalMap {
+        let mut composed = IntervalMap::default();
+        for segment in self.segments() {
+            let offset = segment.offset;
+            for (image, next) in next.split(segment.start + offset..segment.end + offset) {
+                composed.push(Bijection {
+                    start: image.start - offset,
+                    end: image.end - offset,
+                    offset: offset + next,
+                });
+            }
+        }
+
+        composed.pieces.retain(|piece| piece.offset != 0);
+        composed
+    }
+
+    /// Every value mapping into one of `ranges`, sorted with overlapping ranges merged.
+    pub fn preimage(&self, ranges: &[Range<i64>]) -> Vec<Range<i64>> {
+        let mut sources = vec![];
+        for segment in self.segments() {
+            let image = segment.start + segment.offset..segment.end + segment.offset;
+            for range in ranges {
+                let start = image.start.max(range.start);
+                let end = image.end.min(range.end);
+                if start < end {
+                    sources.push(start - segment.offset..end - segment.offset);
+                }
+            }
+        }
+        coalesce(sources)
+    }
+}
+
+fn coalesce(mut ranges: Vec<Range<i64>>) -> Vec<Range<i64>> {
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<i64>> = vec![];
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// One "<source>-to-<destination> map:" block of the almanac.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    source: String,
+    destination: String,
+    /// Each bijection with the number of the line it was read from, sorted by start.
+    bijections: Vec<(usize, Bijection)>,
+}
+
+/// Two bijections of one layer whose source ranges share `range`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlap {
+    pub layer: String,
+    pub lines: (usize, usize),
+    pub range: Range<i64>,
+}
+
+impl fmt::Display for Overlap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} lines {} and {} both map {}..{}",
+            self.layer, self.lines.0, self.lines.1, self.range.start, self.range.end
+        )
+    }
+}
+
+impl Layer {
+    fn parse(line: &Line) -> Result<Self, ParseError> {
+        line.text()
+            .strip_suffix(" map:")
+            .and_then(|name| name.split_once("-to-"))
+            .map(|(source, destination)| Layer {
+                source: source.to_string(),
+                destination: destination.to_string(),
+                bijections: vec![],
             })
-        } else {
-            None
+            .ok_or_else(|| line.malformed(line.text(), "\"<source>-to-<destination> map:\""))
+    }
+
+    /// Adds the bijection on `line`, rejecting it in strict mode when it overlaps an earlier one.
+    fn add(&mut self, line: &Line, mode: Mode) -> Result<(), ParseError> {
+        let bijection = Bijection::parse(line)?;
+        let i = self
+            .bijections
+            .partition_point(|(_, other)| other.start <= bijection.start);
+
+        // Strict layers hold no overlaps, so only the nearest non-empty ranges can collide.
+        if mode == Mode::Strict && bijection.start < bijection.end {
+            let before = self.bijections[..i]
+                .iter()
+                .rev()
+                .find(|(_, b)| b.start < b.end);
+            let after = self.bijections[i..].iter().find(|(_, b)| b.start < b.end);
+            let overlapping = before
+                .filter(|(_, b)| b.end > bijection.start)
+                .or(after.filter(|(_, b)| b.start < bijection.end));
+            if let Some((earlier, _)) = overlapping {
+                let source = line.text().split_whitespace().nth(1);
+                return Err(line.malformed(
+                    source.unwrap_or(line.text()),
+                    format!("a source range not overlapping line {}", earlier),
+                ));
+            }
+        }
+
+        self.bijections.insert(i, (line.number(), bijection));
+        Ok(())
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn map(&self) -> IntervalMap {
+        IntervalMap::new(
+            self.bijections
+                .iter()
+                .map(|&(_, bijection)| bijection)
+                .collect(),
+        )
+    }
+
+    /// Every pair of bijections with overlapping source ranges.
+    pub fn overlaps(&self) -> Vec<Overlap> {
+        let sorted = &self.bijections;
+        let mut overlaps = vec![];
+        for (i, (first, a)) in sorted.iter().enumerate() {
+            for (second, b) in sorted[i + 1..].iter().take_while(|(_, b)| b.start < a.end) {
+                if a.start < a.end && b.start < b.end {
+                    overlaps.push(Overlap {
+                        layer: format!("{}-to-{}", self.source, self.destination),
+                        lines: ((*first).min(*second), (*first).max(*second)),
+                        range: b.start..a.end.min(b.end),
+                    });
+                }
+            }
         }
+        overlaps
+    }
+
+    /// Drops empty bijections and merges those continuing one another with the same offset.
+    pub fn normalize(&mut self) {
+        let mut merged: Vec<(usize, Bijection)> = vec![];
+        let bijections = self.bijections.drain(..);
+        for (line, bijection) in bijections.filter(|(_, b)| b.start < b.end) {
+            match merged.last_mut() {
+                Some((_, last))
+                    if last.end == bijection.start && last.offset == bijection.offset =>
+                {
+                    last.end = last.end.max(bijection.end)
+                }
+                _ => merged.push((line, bijection)),
+            }
+        }
+        self.bijections = merged;
     }
 }
 
 #[derive(Debug)]
 pub struct Data {
     seeds: Vec<i64>,
-    mapping: Vec<Vec<Bijection>>,
+    layers: Vec<Layer>,
 }
 
-impl TryFrom<&str> for Data {
-    type Error = anyhow::Error;
+impl Data {
+    pub fn parse(value: &str, mode: Mode) -> Result<Self, ParseError> {
+        let mut lines = error::lines(DAY, value);
 
-    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
-        let lines: Vec<&str> = value.lines().collect();
-        let seeds = (lines[0].get("seeds: ".len()..))
-            .unwrap_or("")
-            .split(' ')
-            .map(|seed| seed.parse::<i64>().with_context(|| "cannot parse seeds"))
-            .collect::<Result<Vec<i64>>>()?;
-
-        let mut mapping: Vec<Vec<Bijection>> = vec![];
-        let mut map: Vec<Bijection> = vec![];
-
-        for i in 1..lines.len() {
-            let line = lines[i];
-            if !line.is_empty() {
-                if line.ends_with(" map:") && !map.is_empty() {
-                    map.sort_by(|a, b| a.start.cmp(&b.start));
-                    mapping.push(map);
-                    map = vec![];
-                } else if let Some((Ok(codomain), Ok(domain), Ok(span))) = line
-                    .split(' ')
-                    .map(|number| number.parse::<i64>())
-                    .collect_tuple()
-                {
-                    map.push(Bijection {
-                        start: domain,
-                        end: domain + span,
-                        offset: codomain - domain,
-                    });
+        let line = lines
+            .next()
+            .ok_or_else(|| ParseError::missing(DAY, 1, "\"seeds: <numbers>\""))?;
+        let seeds = line
+            .text()
+            .strip_prefix("seeds:")
+            .ok_or_else(|| line.malformed(line.text(), "\"seeds: <numbers>\""))?
+            .split_whitespace()
+            .map(|seed| {
+                seed.parse::<i64>()
+                    .map_err(|_| line.malformed(seed, "a seed number"))
+            })
+            .collect::<Result<Vec<i64>, _>>()?;
+
+        let mut layers: Vec<Layer> = vec![];
+        // Set after a lenient header is rejected, so its lines don't join the layer before it.
+        let mut skipping = false;
+
+        for line in lines.filter(|line| !line.text().is_empty()) {
+            let parsed = if line.text().ends_with(':') {
+                let layer = Layer::parse(&line);
+                skipping = layer.is_err();
+                layer.map(|layer| layers.push(layer))
+            } else if skipping {
+                continue;
+            } else {
+                match layers.last_mut() {
+                    Some(layer) => layer.add(&line, mode),
+                    None => Err(line.malformed(line.text(), "\"<source>-to-<destination> map:\"")),
                 }
+            };
+
+            if let (Err(error), Mode::Strict) = (parsed, mode) {
+                return Err(error);
+            }
+        }
+
+        Ok(Data { seeds, layers })
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn overlaps(&self) -> Vec<Overlap> {
+        self.layers.iter().flat_map(Layer::overlaps).collect()
+    }
+
+    pub fn normalize(&mut self) {
+        self.layers.iter_mut().for_each(Layer::normalize);
+    }
+
+    /// The layers leading from `from` to `to`, found by following category names.
+    fn chain(&self, from: &str, to: &str) -> Result<Vec<&Layer>> {
+        let mut chain = vec![];
+        let mut category = from;
+
+        while category != to {
+            let layer = self
+                .layers
+                .iter()
+                .find(|layer| layer.source == category)
+                .with_context(|| {
+                    format!(
+                        "no map from {} on the way from {} to {}",
+                        category, from, to
+                    )
+                })?;
+            if chain.len() == self.layers.len() {
+                bail!("the maps from {} loop without reaching {}", from, to);
             }
+            chain.push(layer);
+            category = &layer.destination;
         }
 
-        map.sort_by(|a, b| a.start.cmp(&b.start));
-        mapping.push(map);
+        Ok(chain)
+    }
+
+    /// The composed map from category `from` to category `to`.
+    pub fn map(&self, from: &str, to: &str) -> Result<IntervalMap> {
+        Ok(self
+            .chain(from, to)?
+            .into_iter()
+            .fold(IntervalMap::default(), |map, layer| {
+                map.compose(&layer.map())
+            }))
+    }
+
+    pub fn convert(&self, from: &str, to: &str, value: i64) -> Result<i64> {
+        Ok(self.map(from, to)?.apply(value))
+    }
+
+    /// Every layer composed into one map from seed to location.
+    pub fn composed(&self) -> Result<IntervalMap> {
+        self.map("seed", "location")
+    }
+
+    /// The seed ranges whose locations fall within `locations`, found by inverting each layer
+    /// from the last to the first.
+    pub fn seeds_for(&self, locations: Range<i64>) -> Result<Vec<Range<i64>>> {
+        Ok(self
+            .chain("seed", "location")?
+            .into_iter()
+            .rev()
+            .fold(vec![locations], |ranges, layer| {
+                layer.map().preimage(&ranges)
+            }))
+    }
 
-        Ok(Data { seeds, mapping })
+    /// Every seed whose location is `location`.
+    pub fn seeds_at(&self, location: i64) -> Result<Vec<i64>> {
+        Ok(self
+            .seeds_for(location..location + 1)?
+            .into_iter()
+            .flatten()
+            .collect())
     }
 }
 
-pub fn part_one(data: Data) -> Option<i64> {
+impl TryFrom<&str> for Data {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        Data::parse(value, Mode::Strict)
+    }
+}
+
+pub fn part_one(data: &Data) -> Result<i64> {
+    let map = data.composed()?;
     data.seeds
         .iter()
-        .map(|&seed| {
-            data.mapping.iter().fold(seed, |seed, map| {
-                map.iter()
-                    .filter_map(|bijection| bijection.location(seed))
-                    .min_by(|a, b| a.supremum.cmp(&b.supremum))
-                    .map_or(seed, |location| location.result)
-            })
-        })
+        .map(|&seed| map.apply(seed))
         .min()
+        .context("no seeds")
 }
 
-pub fn part_two(data: Data) -> Option<i64> {
-    data.seeds
+pub fn part_two(data: &Data) -> Result<i64> {
+    let seeds: Vec<Range<i64>> = data
+        .seeds
         .iter()
-        .tuples::<(&i64, &i64)>()
-        .flat_map(|(&start, &length)| {
-            let mut results = vec![];
-            let mut seed = start;
-            while seed < (start + length) {
-                let (result, supremum) =
-                    data.mapping
-                        .iter()
-                        .fold((seed, seed + length), |(seed, supremum), map| {
-                            map.iter()
-                                .filter_map(|bijection| bijection.location(seed))
-                                .min_by(|a, b| a.supremum.cmp(&b.supremum))
-                                .map_or((seed, supremum), |location| {
-                                    (location.result, supremum.min(location.supremum))
-                                })
-                        });
-                results.push(result);
-                seed = seed + supremum;
-            }
-            results
-        })
-        .min()
+        .tuples()
+        .map(|(&start, &length)| start..start + length)
+        .collect();
+
+    data.composed()?
+        .apply_ranges(&seeds)
+        .first()
+        .map(|range| range.start)
+        .context("no seeds")
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Data;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Ok(Data::try_from(input)?)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(part_one(input)?.into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(part_two(input)?.into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
 
     #[test]
     fn part_1_example() -> Result<()> {
-        let input = include_str!("../res/05.example");
-        assert_eq!(part_one(Data::try_from(input)?), Some(35));
+        let input = load(5, Variant::Example(1))?;
+        assert_eq!(part_one(&Data::try_from(input.as_str())?)?, 35);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/05.actual");
-        assert_eq!(part_one(Data::try_from(input)?), Some(486_613_012));
+        let input = load(5, Variant::Actual)?;
+        assert_eq!(part_one(&Data::try_from(input.as_str())?)?, 486_613_012);
         Ok(())
     }
 
     #[test]
     fn part_2_example() -> Result<()> {
-        let input = include_str!("../res/05.example");
-        assert_eq!(part_two(Data::try_from(input)?), Some(46));
+        let input = load(5, Variant::Example(1))?;
+        assert_eq!(part_two(&Data::try_from(input.as_str())?)?, 46);
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/05.actual");
-        assert_eq!(part_two(Data::try_from(input)?), Some(56_931_769));
+        let input = load(5, Variant::Actual)?;
+        assert_eq!(part_two(&Data::try_from(input.as_str())?)?, 56_931_769);
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() -> Result<()> {
+        let input = "seeds: 79 14\n\nseed-to-soil map:\n50 98 2\n52 50\n";
+        let error = Data::parse(input, Mode::Strict).unwrap_err();
+        assert_eq!((error.line(), error.column()), (5, Some(1)));
+        assert_eq!(error.snippet(), Some("52 50"));
+        let data = Data::parse(input, Mode::Lenient)?;
+        assert_eq!(data.convert("seed", "soil", 98)?, 50);
+        assert!(part_one(&data).is_err());
+
+        let input = "seeds: 1\n\nseed-to-soil map:\n10 0 1\n\nsoil-to-fertilizer mapp:\n100 1 2\n";
+        assert_eq!(Data::parse(input, Mode::Strict).unwrap_err().line(), 6);
+        let data = Data::parse(input, Mode::Lenient)?;
+        assert_eq!(data.layers().len(), 1);
+        assert_eq!(data.convert("seed", "soil", 1)?, 1);
+        assert_eq!(data.convert("seed", "soil", 0)?, 10);
+        Ok(())
+    }
+
+    #[test]
+    fn composition() -> Result<()> {
+        let input = load(5, Variant::Example(1))?;
+        let data = Data::try_from(input.as_str())?;
+        let map = data.composed()?;
+        let locations: Vec<i64> = [79, 14, 55, 13].map(|seed| map.apply(seed)).to_vec();
+        assert_eq!(locations, [82, 43, 86, 35]);
+
+        let layers: Vec<IntervalMap> = data.layers().iter().map(Layer::map).collect();
+        for seed in 0..100 {
+            let stepped = layers.iter().fold(seed, |value, layer| layer.apply(value));
+            assert_eq!(map.apply(seed), stepped);
+        }
+
+        let images = map.apply_ranges(&[79..93, 55..68]);
+        assert_eq!(images.first().map(|range| range.start), Some(46));
+        assert_eq!(
+            images
+                .iter()
+                .map(|range| range.end - range.start)
+                .sum::<i64>(),
+            27
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn inverse() -> Result<()> {
+        let input = load(5, Variant::Example(1))?;
+        let data = Data::try_from(input.as_str())?;
+        let map = data.composed()?;
+        for (seed, location) in [(79, 82), (14, 43), (55, 86), (13, 35)] {
+            let seeds = data.seeds_at(location)?;
+            assert!(seeds.contains(&seed));
+            assert!(seeds.iter().all(|&seed| map.apply(seed) == location));
+        }
+
+        let seeds = data.seeds_for(46..60)?;
+        assert_eq!(seeds, map.preimage(&[46..50, 50..60]));
+        assert!(seeds
+            .iter()
+            .cloned()
+            .flatten()
+            .all(|seed| (46..60).contains(&map.apply(seed))));
+        assert_eq!(
+            seeds
+                .iter()
+                .map(|range| range.end - range.start)
+                .sum::<i64>(),
+            14
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn categories() -> Result<()> {
+        let input = load(5, Variant::Example(1))?;
+        let data = Data::try_from(input.as_str())?;
+        assert_eq!(data.convert("soil", "humidity", 81)?, 78);
+        assert_eq!(data.convert("water", "water", 81)?, 81);
+        assert!(data.convert("location", "seed", 82).is_err());
+
+        let mut blocks: Vec<&str> = input.trim_end().split("\n\n").collect();
+        blocks[1..].reverse();
+        let shuffled = Data::try_from(blocks.join("\n\n").as_str())?;
+        assert_eq!(part_one(&shuffled)?, 35);
+
+        blocks.retain(|block| !block.starts_with("light-to-temperature"));
+        let broken = Data::try_from(blocks.join("\n\n").as_str())?;
+        let error = part_one(&broken).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "no map from light on the way from seed to location"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pieces() {
+        let map = IntervalMap::new(vec![Bijection::new(10..20, 5), Bijection::new(0..5, -1)]);
+        assert_eq!((map.apply(3), map.apply(7), map.apply(12)), (2, 7, 17));
+        let piece = map.pieces()[1];
+        assert_eq!((piece.range(), piece.offset()), (10..20, 5));
+    }
+
+    #[test]
+    fn overlaps() -> Result<()> {
+        let input = "seeds: 1\n\nseed-to-soil map:\n10 0 5\n20 3 4\n30 20 2\n";
+        let error = Data::parse(input, Mode::Strict).unwrap_err();
+        assert_eq!((error.line(), error.column()), (5, Some(4)));
+        assert_eq!(error.snippet(), Some("3"));
+
+        let data = Data::parse(input, Mode::Lenient)?;
+        let overlaps = data.overlaps();
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(
+            overlaps[0].to_string(),
+            "seed-to-soil lines 4 and 5 both map 3..5"
+        );
+
+        let input = "seeds: 1\n\nseed-to-soil map:\n12 2 3\n10 0 2\n40 20 2\n";
+        let mut data = Data::try_from(input)?;
+        let map = data.layers()[0].map();
+        data.normalize();
+        let layer = &data.layers()[0];
+        assert_eq!(
+            layer.bijections,
+            [
+                (
+                    5,
+                    Bijection {
+                        start: 0,
+                        end: 5,
+                        offset: 10
+                    }
+                ),
+                (
+                    6,
+                    Bijection {
+                        start: 20,
+                        end: 22,
+                        offset: 20
+                    }
+                ),
+            ]
+        );
+        assert_eq!(layer.map(), map);
+
+        let input = "seeds: 1\n\nseed-to-soil map:\n10 0 5\n15 5 -3\n";
+        let error = Data::parse(input, Mode::Strict).unwrap_err();
+        assert_eq!((error.line(), error.snippet()), (5, Some("-3")));
+
+        for input in [
+            "seeds: 1\n\nseed-to-soil map:\n10 0 5\n15 5 -3\n",
+            "seeds: 1\n\nseed-to-soil map:\n30 5 0\n15 5 3\n10 0 5\n99 8 0\n",
+        ] {
+            let mut data = Data::parse(input, Mode::Lenient)?;
+            let map = data.layers()[0].map();
+            data.normalize();
+            assert_eq!(data.layers()[0].map(), map);
+            assert_eq!(map.apply(4), 14);
+        }
+
+        let input = "seeds: 1\n\nseed-to-soil map:\n30 5 0\n15 5 3\n10 0 5\n99 8 0\n";
+        let mut data = Data::try_from(input)?;
+        data.normalize();
+        assert_eq!(data.layers()[0].bijections, [(6, Bijection::new(0..8, 10))]);
         Ok(())
     }
 }
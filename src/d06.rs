@@ -1,126 +1,444 @@
+use crate::error::{self, Line, Mode, ParseError};
+use crate::{Answer, Solution};
+use anyhow::{bail, Context, Result};
+use num_bigint::BigInt;
+use std::collections::BTreeSet;
+
+const DAY: u8 = 6;
+
 #[derive(Debug)]
 pub struct Heat {
     time: i64,
     distance: i64,
 }
 
+impl Heat {
+    pub fn new(time: i64, distance: i64) -> Self {
+        Heat { time, distance }
+    }
+
+    /// The number of winning charge times, counted as for the equivalent [`Race`].
+    pub fn wins(&self) -> u64 {
+        let race = Race::new(BigInt::from(self.time), BigInt::from(self.distance));
+        // At most every charge in `0..=time` wins, which always fits.
+        u64::try_from(race.wins()).expect("wins exceed u64")
+    }
+}
+
+/// A race whose time and distance may exceed `i64`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Race {
+    time: BigInt,
+    distance: BigInt,
+}
+
+impl Race {
+    pub fn new(time: BigInt, distance: BigInt) -> Self {
+        Race { time, distance }
+    }
+
+    /// The number of charge times `c` in `0..=time` with `c * (time - c) > distance`.
+    ///
+    /// The travelled distance is a downward parabola symmetric about `time / 2`, so the winners
+    /// run from the smallest winning charge `lo` to `time - lo`. `lo` is estimated from the
+    /// quadratic's lower root with an integer square root and then corrected exactly.
+    pub fn wins(&self) -> BigInt {
+        let (time, distance) = (&self.time, &self.distance);
+        let travel = |charge: &BigInt| charge * (time - charge);
+        let zero = BigInt::from(0);
+        let half: BigInt = time / 2;
+
+        if time < &zero || travel(&half) <= *distance {
+            return zero;
+        }
+
+        let discriminant: BigInt = time * time - distance * 4;
+        let discriminant = discriminant.max(zero.clone());
+        let lo: BigInt = (time - discriminant.sqrt()) / 2;
+        let mut lo = lo.clamp(zero.clone(), half);
+        while lo > zero && travel(&(&lo - 1)) > *distance {
+            lo -= 1;
+        }
+        while travel(&lo) <= *distance {
+            lo += 1;
+        }
+
+        time - lo * 2 + 1
+    }
+}
+
+/// One "<label>: <numbers>" line of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    label: String,
+    tokens: Vec<String>,
+}
+
+impl Row {
+    /// Reads the row along with the columns whose tokens aren't numbers, which only lenient
+    /// mode lets through.
+    fn parse(line: &Line, mode: Mode) -> Result<(Self, Vec<usize>), ParseError> {
+        let (label, numbers) = line
+            .text()
+            .split_once(':')
+            .filter(|(label, _)| !label.trim().is_empty())
+            .ok_or_else(|| line.malformed(line.text(), "\"<label>: <numbers>\""))?;
+
+        let (mut tokens, mut bad) = (vec![], vec![]);
+        for (column, token) in numbers.split_whitespace().enumerate() {
+            match (token.parse::<BigInt>(), mode) {
+                (Ok(_), _) => {}
+                (Err(_), Mode::Strict) => return Err(line.malformed(token, "a number")),
+                (Err(_), Mode::Lenient) => bad.push(column),
+            }
+            tokens.push(token.to_string());
+        }
+
+        let row = Row {
+            label: label.trim().to_string(),
+            tokens,
+        };
+        Ok((row, bad))
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The numbers as written, however large.
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// The numbers as `i64`, failing on the first that doesn't fit.
+    pub fn values(&self) -> Result<Vec<i64>> {
+        self.tokens
+            .iter()
+            .map(|token| value(&self.label, token))
+            .collect()
+    }
+}
+
+fn value(label: &str, token: &str) -> Result<i64> {
+    token
+        .parse::<i64>()
+        .with_context(|| format!("{} {} doesn't fit an i64", label, token))
+}
+
 #[derive(Debug)]
 pub struct Data {
-    heats: Vec<Heat>,
+    rows: Vec<Row>,
 }
 
-impl TryFrom<&str> for Data {
-    type Error = anyhow::Error;
+impl Data {
+    const TIME: &'static str = "Time";
+    const DISTANCE: &'static str = "Distance";
+    /// Reads any number of labelled rows, of which "Time" and "Distance" are required in either
+    /// mode.
+    /// Reads any number of labelled rows, of which "Time" and "Distance" are required.
+    ///
+    /// In strict mode every token must be a number, labels must be unique and every row must
+    /// have as many columns as the first. Otherwise bad lines are skipped, a bad token drops
+    /// its whole column from every row and the heats are cut to the shorter of the two rows.
+    pub fn parse(value: &str, mode: Mode) -> Result<Self, ParseError> {
+        let mut rows: Vec<Row> = vec![];
+        let mut bad = BTreeSet::new();
+        let mut count = 0;
 
-    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
-        fn parse_values(prefix: &str, line: &str) -> Vec<i64> {
-            line.get(prefix.len()..)
-                .unwrap_or("")
-                .split(' ')
-                .map(|e| e.trim().parse::<i64>())
-                .flatten()
-                .collect()
+        for line in error::lines(DAY, value) {
+            count = line.number();
+            if line.text().trim().is_empty() {
+                continue;
+            }
+
+            let row = match (Row::parse(&line, mode), mode) {
+                (Ok((row, columns)), _) => {
+                    bad.extend(columns);
+                    row
+                }
+                (Err(error), Mode::Strict) => return Err(error),
+                (Err(_), Mode::Lenient) => continue,
+            };
+
+            if mode == Mode::Strict {
+                let label = line.text().split(':').next().unwrap_or_default();
+                if rows.iter().any(|other| other.label == row.label) {
+                    return Err(line.malformed(label, "a label not already used"));
+                }
+                if let Some(first) = rows.first() {
+                    if first.tokens.len() != row.tokens.len() {
+                        let numbers = &line.text()[label.len() + 1..];
+                        return Err(line.malformed(
+                            numbers,
+                            format!(
+                                "{} numbers like the {} row",
+                                first.tokens.len(),
+                                first.label
+                            ),
+                        ));
+                    }
+                }
+            }
+
+            rows.push(row);
+        }
+
+        for row in &mut rows {
+            let tokens = std::mem::take(&mut row.tokens).into_iter().enumerate();
+            row.tokens = tokens
+                .filter(|(column, _)| !bad.contains(column))
+                .map(|(_, token)| token)
+                .collect();
+        }
+
+        for label in [Data::TIME, Data::DISTANCE] {
+            if !rows.iter().any(|row| row.label == label) {
+                return Err(ParseError::missing(
+                    DAY,
+                    count + 1,
+                    format!("a \"{}:\" row", label),
+                ));
+            }
+        }
+
+        Ok(Data { rows })
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    /// The row labelled `label`.
+    pub fn row(&self, label: &str) -> Option<&Row> {
+        self.rows.iter().find(|row| row.label == label)
+    }
+
+    /// The tokens of a required row, cut to the columns both required rows have.
+    fn columns(&self, label: &str) -> &[String] {
+        let tokens = |label: &str| self.row(label).map_or(&[][..], Row::tokens);
+        let columns = tokens(Data::TIME).len().min(tokens(Data::DISTANCE).len());
+        &tokens(label)[..columns]
+    }
+
+    /// The separate heats, of which there must be at least one and whose times and distances
+    /// must fit an `i64`.
+    pub fn heats(&self) -> Result<Vec<Heat>> {
+        if self.columns(Data::TIME).is_empty() {
+            bail!("no heat has both a time and a distance");
         }
 
-        let lines: Vec<&str> = value.lines().collect();
+        self.columns(Data::TIME)
+            .iter()
+            .zip(self.columns(Data::DISTANCE))
+            .map(|(time, distance)| {
+                Ok(Heat::new(
+                    value(Data::TIME, time)?,
+                    value(Data::DISTANCE, distance)?,
+                ))
+            })
+            .collect()
+    }
 
-        let times = lines
-            .get(0)
-            .map_or(vec![], |line| parse_values("Times:", line));
+    /// The single race read by ignoring the spaces between heats.
+    pub fn race(&self) -> Result<Race> {
+        let number = |label: &str| {
+            let digits = self.columns(label).concat();
+            digits
+                .parse::<BigInt>()
+                .with_context(|| format!("couldn't read {:?} as one race {}", digits, label))
+        };
+        Ok(Race::new(number(Data::TIME)?, number(Data::DISTANCE)?))
+    }
+}
 
-        let distances = lines
-            .get(1)
-            .map_or(vec![], |line| parse_values("Distance:", line));
+impl TryFrom<&str> for Data {
+    type Error = ParseError;
 
-        Ok(Data {
-            heats: times
-                .iter()
-                .zip(distances.iter())
-                .map(|(&time, &distance)| Heat { time, distance })
-                .collect(),
-        })
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        Data::parse(value, Mode::Strict)
     }
 }
 
-pub fn part_one(data: Data) -> i64 {
-    data.heats
-        .iter()
-        .map(|heat| {
-            i64::try_from(
-                (0..=heat.time)
-                    .map(|charge_up| (charge_up * (heat.time - charge_up)))
-                    .filter(|e| e > &heat.distance)
-                    .count(),
-            )
-            .unwrap_or(0)
-        })
-        .product()
+pub fn part_one(data: &Data) -> Result<u64> {
+    Ok(data.heats()?.iter().map(Heat::wins).product())
 }
 
-pub fn part_two(data: Data) -> i64 {
-    let (time, distance) =
-        data.heats
-            .iter()
-            .fold((String::new(), String::new()), |(time, distance), next| {
-                (
-                    format!("{}{}", time, next.time).to_string(),
-                    format!("{}{}", distance, next.distance).to_string(),
-                )
-            });
-
-    let time: i64 = time.parse().unwrap_or(0);
-    let distance: i64 = distance.parse().unwrap_or(0);
-
-    let lower = i64::try_from(
-        (0..=(time / 2))
-            .map(|charge_up| (charge_up * (time - charge_up)))
-            .take_while(|e| e <= &distance)
-            .count(),
-    )
-    .unwrap_or(0);
-
-    let upper = i64::try_from(
-        ((time / 2)..=time)
-            .rev()
-            .map(|charge_up| charge_up * (time - charge_up))
-            .take_while(|e| e <= &distance)
-            .count(),
-    )
-    .unwrap_or(0);
+pub fn part_two(data: &Data) -> Result<BigInt> {
+    Ok(data.race()?.wins())
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Data;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Ok(Data::try_from(input)?)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(part_one(input)?.into())
+    }
 
-    time - (lower + upper) + 1
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(part_two(input)?.into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
     use anyhow::Result;
+    use num_bigint::BigInt;
 
     #[test]
     fn part_1_example() -> Result<()> {
-        let input = include_str!("../res/06.example");
-        assert_eq!(part_one(Data::try_from(input)?), 288);
+        let input = load(6, Variant::Example(1))?;
+        assert_eq!(part_one(&Data::try_from(input.as_str())?)?, 288);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/06.actual");
-        assert_eq!(part_one(Data::try_from(input)?), 4_568_778);
+        let input = load(6, Variant::Actual)?;
+        assert_eq!(part_one(&Data::try_from(input.as_str())?)?, 4_568_778);
         Ok(())
     }
 
     #[test]
     fn part_2_example() -> Result<()> {
-        let input = include_str!("../res/06.example");
-        assert_eq!(part_two(Data::try_from(input)?), 71_503);
+        let input = load(6, Variant::Example(1))?;
+        assert_eq!(
+            part_two(&Data::try_from(input.as_str())?)?,
+            BigInt::from(71_503)
+        );
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/06.actual");
-        assert_eq!(part_two(Data::try_from(input)?), 28_973_936);
+        let input = load(6, Variant::Actual)?;
+        assert_eq!(
+            part_two(&Data::try_from(input.as_str())?)?,
+            BigInt::from(28_973_936)
+        );
+        Ok(())
+    }
+
+    /// Counts the winning charge times one by one.
+    fn scan(heat: &Heat) -> u64 {
+        (0..=heat.time)
+            .filter(|charge| charge * (heat.time - charge) > heat.distance)
+            .count() as u64
+    }
+
+    /// Counts the losing charge times in from either end, as part two used to.
+    fn sides(heat: &Heat) -> i64 {
+        let (time, distance) = (heat.time, heat.distance);
+        let travel = |charge: i64| charge * (time - charge);
+        let lower = (0..=time / 2)
+            .map(travel)
+            .take_while(|&e| e <= distance)
+            .count() as i64;
+        let upper = (time / 2..=time)
+            .rev()
+            .map(travel)
+            .take_while(|&e| e <= distance)
+            .count() as i64;
+        time - (lower + upper) + 1
+    }
+
+    #[test]
+    fn closed_form() {
+        for time in -2..80 {
+            for distance in -3..=time * time / 4 + 2 {
+                let heat = Heat::new(time, distance);
+                assert_eq!(heat.wins(), scan(&heat), "{:?}", heat);
+                // Without a winner the two sides overlap at the midpoint and miscount.
+                if heat.wins() > 0 {
+                    assert_eq!(heat.wins() as i64, sides(&heat), "{:?}", heat);
+                }
+            }
+        }
+
+        assert_eq!(Heat::new(i64::MAX, 0).wins(), i64::MAX as u64 - 1);
+        assert_eq!(Heat::new(i64::MAX, -1).wins(), i64::MAX as u64 + 1);
+        let time = i64::MAX as i128;
+        for distance in [i64::MAX, i64::MAX / 3, 1] {
+            let heat = Heat::new(i64::MAX, distance);
+            let lo = (time + 1 - heat.wins() as i128) / 2;
+            let travel = |charge: i128| charge * (time - charge);
+            assert!(travel(lo) > distance as i128);
+            assert!(travel(lo - 1) <= distance as i128);
+        }
+    }
+
+    #[test]
+    fn big_races() -> Result<()> {
+        for (time, distance) in [(30, 200), (71_530, 940_200), (7, 9), (0, 0), (10, -1)] {
+            let race = Race::new(BigInt::from(time), BigInt::from(distance));
+            assert_eq!(race.wins(), BigInt::from(Heat::new(time, distance).wins()));
+        }
+
+        let input = "Time: 9223372036854775807 10\nDistance: 1 0\n";
+        let race = Data::try_from(input)?.race()?;
+        let time: BigInt = "922337203685477580710".parse()?;
+        assert_eq!(race.wins(), time.clone() - 1);
+        assert_eq!(part_two(&Data::try_from(input)?)?, time - 1);
+
+        assert!(part_two(&Data::try_from("Time: 7 -1\nDistance: 9 1\n")?).is_err());
+
+        let data = Data::try_from("Time: 99999999999999999999\nDistance: 5\n")?;
+        assert!(part_one(&data).is_err());
+        let time: BigInt = "99999999999999999999".parse()?;
+        assert_eq!(part_two(&data)?, time - 1);
+        Ok(())
+    }
+
+    #[test]
+    fn rows() -> Result<()> {
+        let data = Data::try_from("Time: 7 15\nDistance: 9 40\nWind: 1 2\n")?;
+        assert_eq!(data.rows().len(), 3);
+        assert_eq!(
+            data.row("Wind").map(Row::values).transpose()?,
+            Some(vec![1, 2])
+        );
+        assert_eq!(part_one(&data)?, 32);
+
+        let error = Data::try_from("Time: 7 x\nDistance: 9 40\n").unwrap_err();
+        assert_eq!((error.line(), error.column()), (1, Some(9)));
+
+        let error = Data::try_from("Time: 7 15\nDistance: 9\n").unwrap_err();
+        assert_eq!((error.line(), error.snippet()), (2, Some("9")));
+
+        let error = Data::try_from("Time: 7 15\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "day 6, line 2: expected a \"Distance:\" row but the input ended"
+        );
+
+        let data = Data::parse("Time: 7 x 15\nDistance: 9 5 40\n", Mode::Lenient)?;
+        assert_eq!(part_one(&data)?, 32);
+        assert_eq!(
+            data.race()?,
+            Race::new(BigInt::from(715), BigInt::from(940))
+        );
+
+        let data = Data::parse("Time: 7 15 30\nDistance: 9 4o 200 1\n", Mode::Lenient)?;
+        assert_eq!(
+            data.row("Time").map(Row::tokens),
+            Some(&["7", "30"].map(String::from)[..])
+        );
+        assert_eq!(part_one(&data)?, 36);
+
+        for input in ["Time: 7 15\n", "Time: 7 15\nDistance 9 40\n"] {
+            let error = Data::parse(input, Mode::Lenient).unwrap_err();
+            assert_eq!(error.line(), input.lines().count() + 1);
+        }
+        let data = Data::parse("Time: 7 x\nDistance: x 40\n", Mode::Lenient)?;
+        assert!(part_one(&data).is_err());
+        assert!(part_two(&data).is_err());
         Ok(())
     }
 }
@@ -1,181 +1,458 @@
+use crate::error::{self, Line, ParseError};
+use crate::{Answer, Solution};
 use anyhow::{bail, Result};
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Draw {
-    HighCard { cards: [i8; 5] },
-    OnePair { cards: [i8; 5] },
-    TwoPair { cards: [i8; 5] },
-    ThreeKind { cards: [i8; 5] },
-    FullHouse { cards: [i8; 5] },
-    FourKind { cards: [i8; 5] },
-    FiveKind { cards: [i8; 5] },
-}
-
-impl Draw {
-    fn new(hand: &str, mapping: &HashMap<char, i8>) -> Result<Self> {
-        let mut counts: HashMap<char, i8> = mapping.iter().map(|(&k, _v)| (k, 0i8)).collect();
-        let mut draw = [-1; 5];
-        let mut j = 0;
-
-        for (i, c) in hand.chars().enumerate() {
-            match counts.get(&c) {
-                Some(v) => counts.insert(c, v + 1),
-                None => bail!("could not find count for {}", c),
-            };
-            match mapping.get(&c) {
-                Some(v) => {
-                    if *v == -1 {
-                        j = j + 1;
-                    }
-                    draw[i] = *v
-                }
-                None => bail!("could not find mapping for {}", c),
-            };
-        }
+const DAY: u8 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Kind {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeKind,
+    FullHouse,
+    FourKind,
+    FiveKind,
+}
 
-        let mut counts: Vec<i8> = counts.iter().map(|(_key, &value)| value).collect();
-        counts.sort_by(|a, b| b.cmp(a));
+impl Kind {
+    /// Classifies a hand by the sizes of its two largest groups of matching cards.
+    fn from_groups(largest: usize, second: usize) -> Self {
+        match (largest, second) {
+            (5.., _) => Kind::FiveKind,
+            (4, _) => Kind::FourKind,
+            (3, 2..) => Kind::FullHouse,
+            (3, _) => Kind::ThreeKind,
+            (2, 2..) => Kind::TwoPair,
+            (2, _) => Kind::OnePair,
+            _ => Kind::HighCard,
+        }
+    }
+}
 
-        let counts: (i8, i8, i8) = match counts.into_iter().next_tuple() {
-            Some((a, b)) => (a, b, j),
-            None => bail!("could not create top two counts"),
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Kind::HighCard => "high card",
+            Kind::OnePair => "one pair",
+            Kind::TwoPair => "two pair",
+            Kind::ThreeKind => "three of a kind",
+            Kind::FullHouse => "full house",
+            Kind::FourKind => "four of a kind",
+            Kind::FiveKind => "five of a kind",
         };
+        write!(f, "{}", name)
+    }
+}
+
+/// How hands are read and ranked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// The cards from weakest to strongest, used to break ties between hands of the same kind.
+    order: Vec<char>,
+    /// Cards that join whichever group makes the hand strongest.
+    wild: HashSet<char>,
+    size: usize,
+}
+
+impl Rules {
+    /// Cards ranked weakest first in `order`, of which those in `wild` are wild, dealt in hands
+    /// of `size`.
+    pub fn new(order: &str, wild: &str, size: usize) -> Result<Self> {
+        let rules = Rules::build(order, wild, size);
 
-        match counts {
-            (5, 0, _) => Ok(Draw::FiveKind { cards: draw }),
-            (4, 1, 4) => Ok(Draw::FiveKind { cards: draw }),
-            (4, 1, 1) => Ok(Draw::FiveKind { cards: draw }),
-            (4, 1, 0) => Ok(Draw::FourKind { cards: draw }),
-            (3, 2, 3) => Ok(Draw::FiveKind { cards: draw }),
-            (3, 2, 2) => Ok(Draw::FiveKind { cards: draw }),
-            (3, 2, 0) => Ok(Draw::FullHouse { cards: draw }),
-            (3, _, 3) => Ok(Draw::FourKind { cards: draw }),
-            (3, _, 1) => Ok(Draw::FourKind { cards: draw }),
-            (3, _, 0) => Ok(Draw::ThreeKind { cards: draw }),
-            (2, 2, 2) => Ok(Draw::FourKind { cards: draw }),
-            (2, 2, 1) => Ok(Draw::FullHouse { cards: draw }),
-            (2, 2, 0) => Ok(Draw::TwoPair { cards: draw }),
-            (2, _, 2) => Ok(Draw::ThreeKind { cards: draw }),
-            (2, _, 1) => Ok(Draw::ThreeKind { cards: draw }),
-            (2, _, 0) => Ok(Draw::OnePair { cards: draw }),
-            (1, _, 1) => Ok(Draw::OnePair { cards: draw }),
-            _ => Ok(Draw::HighCard { cards: draw }),
+        if size == 0 {
+            bail!("hands must hold at least one card");
         }
+        if let Some(card) = rules.order.iter().duplicates().next() {
+            bail!("card {:?} is ranked more than once", card);
+        }
+        if let Some(card) = rules
+            .wild
+            .iter()
+            .find(|&&card| rules.strength(card).is_none())
+        {
+            bail!("wild card {:?} isn't ranked", card);
+        }
+
+        Ok(rules)
+    }
+
+    fn build(order: &str, wild: &str, size: usize) -> Self {
+        Rules {
+            order: order.chars().collect(),
+            wild: wild.chars().collect(),
+            size,
+        }
+    }
+
+    pub fn standard() -> Self {
+        Rules::build("23456789TJQKA", "", 5)
+    }
+
+    /// Jacks are jokers: wild, and weaker than any other card.
+    pub fn jokers() -> Self {
+        Rules::build("J23456789TQKA", "J", 5)
+    }
+
+    fn strength(&self, card: char) -> Option<usize> {
+        self.order.iter().position(|&c| c == card)
+    }
+
+    /// The kind of `cards` with every wild card added to the largest group of other cards.
+    pub fn classify(&self, cards: &[char]) -> Kind {
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        for card in cards.iter().filter(|card| !self.wild.contains(card)) {
+            *counts.entry(*card).or_default() += 1;
+        }
+
+        let mut groups: Vec<usize> = counts.into_values().collect();
+        groups.sort_by(|a, b| b.cmp(a));
+
+        let wild = cards.len() - groups.iter().sum::<usize>();
+        let largest = groups.first().copied().unwrap_or(0) + wild;
+        Kind::from_groups(largest, groups.get(1).copied().unwrap_or(0))
+    }
+
+    /// The card the wild cards in `cards` stand in for: the strongest of the largest group, or
+    /// the strongest card overall when every card is wild. `None` when no card is wild.
+    pub fn substitute(&self, cards: &[char]) -> Option<char> {
+        if !cards.iter().any(|card| self.wild.contains(card)) {
+            return None;
+        }
+
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        for card in cards.iter().filter(|card| !self.wild.contains(card)) {
+            *counts.entry(*card).or_default() += 1;
+        }
+
+        counts
+            .into_iter()
+            .max_by_key(|&(card, count)| (count, self.strength(card)))
+            .map(|(card, _)| card)
+            .or_else(|| {
+                self.order
+                    .iter()
+                    .rev()
+                    .find(|card| !self.wild.contains(card))
+                    .copied()
+            })
+    }
+}
+
+/// A hand's kind followed by the strength of each card, so draws order the way hands rank.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Draw {
+    kind: Kind,
+    cards: Vec<usize>,
+}
+
+impl Rules {
+    /// Classifies `hand`, reporting cards these rules don't know and hands of the wrong size.
+    fn draw(&self, hand: &Hand) -> Result<Draw, ParseError> {
+        let line = Line::new(Some(DAY), hand.line, &hand.text);
+        let cards: Vec<char> = hand.cards().chars().collect();
+
+        if let Some((i, card)) = hand
+            .cards()
+            .char_indices()
+            .find(|&(_, c)| self.strength(c).is_none())
+        {
+            return Err(line.malformed_at(i, card.len_utf8(), "a card"));
+        }
+
+        if cards.len() != self.size {
+            return Err(line.malformed_at(
+                0,
+                hand.cards().len(),
+                format!("a hand of {} cards", self.size),
+            ));
+        }
+
+        Ok(Draw {
+            kind: self.classify(&cards),
+            cards: cards
+                .iter()
+                .filter_map(|&card| self.strength(card))
+                .collect(),
+        })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Hand {
-    draw: Draw,
+    line: usize,
+    /// The whole line, kept so the rules can point into it.
+    text: String,
     bid: i64,
 }
 
-fn hands(value: &str, mapping: &HashMap<char, i8>) -> Result<Vec<Hand>> {
-    let mut hands: Vec<Hand> = value
-        .lines()
-        .map(|line| {
-            if let Some((cards, bid)) = line.split_once(' ') {
-                if let Ok(bid) = bid.parse::<i64>() {
-                    if let Ok(draw) = Draw::new(cards, mapping) {
-                        Some(Hand { draw, bid })
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
+impl Hand {
+    fn parse(line: &Line) -> Result<Self, ParseError> {
+        let (_, bid) = line
+            .text()
+            .split_once(' ')
+            .ok_or_else(|| line.malformed(line.text(), "\"<cards> <bid>\""))?;
+
+        let bid = bid
+            .parse::<i64>()
+            .map_err(|_| line.malformed(bid, "a bid"))?;
+
+        Ok(Hand {
+            line: line.number(),
+            text: line.text().to_string(),
+            bid,
         })
-        .flatten()
-        .collect();
+    }
 
-    hands.sort_by(|a, b| a.draw.cmp(&b.draw));
+    /// The cards, which open the line.
+    pub fn cards(&self) -> &str {
+        self.text
+            .split_once(' ')
+            .map_or(&self.text, |(cards, _)| cards)
+    }
+}
 
-    Ok(hands)
+/// Every non-blank line read as a hand.
+fn parse_lines(value: &str) -> impl Iterator<Item = Result<Hand, ParseError>> + '_ {
+    error::lines(DAY, value)
+        .filter(|line| !line.text().is_empty())
+        .map(|line| Hand::parse(&line))
 }
 
-pub fn part_one(input: &str) -> Result<i64> {
-    let mapping = HashMap::from([
-        ('2', 0),
-        ('3', 1),
-        ('4', 2),
-        ('5', 3),
-        ('6', 4),
-        ('7', 5),
-        ('8', 6),
-        ('9', 7),
-        ('T', 8),
-        ('J', 9),
-        ('Q', 10),
-        ('K', 11),
-        ('A', 12),
-    ]);
-
-    Ok(hands(input, &mapping)?
-        .iter()
+fn hands(value: &str) -> Result<Vec<Hand>> {
+    Ok(parse_lines(value).collect::<Result<Vec<Hand>, _>>()?)
+}
+
+/// The total winnings when `hands` are ranked by `rules`.
+pub fn winnings(hands: &[Hand], rules: &Rules) -> Result<i64> {
+    rank(hands, rules, vec![]).winnings()
+}
+
+/// A ranked hand and how it was classified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub line: usize,
+    pub cards: String,
+    pub kind: Kind,
+    /// The card any wild cards were counted as.
+    pub substitute: Option<char>,
+    pub rank: usize,
+    pub bid: i64,
+    pub winnings: i64,
+}
+
+/// Every ranked hand, weakest first, along with the lines that couldn't be read as hands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub entries: Vec<Entry>,
+    pub rejected: Vec<ParseError>,
+}
+
+impl Report {
+    pub fn total(&self) -> i64 {
+        self.entries.iter().map(|entry| entry.winnings).sum()
+    }
+
+    /// The total winnings, provided no line was rejected.
+    pub fn winnings(&self) -> Result<i64> {
+        match self.rejected.first() {
+            Some(error) => Err(error.clone().into()),
+            None => Ok(self.total()),
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>5} {:>5}  {:<8} {:<16} {:<10} {:>8} {:>12}",
+            "rank", "line", "cards", "kind", "wild as", "bid", "winnings"
+        )?;
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{:>5} {:>5}  {:<8} {:<16} {:<10} {:>8} {:>12}",
+                entry.rank,
+                entry.line,
+                entry.cards,
+                entry.kind.to_string(),
+                entry.substitute.map_or("-".to_string(), String::from),
+                entry.bid,
+                entry.winnings
+            )?;
+        }
+        writeln!(f, "total winnings {}", self.total())?;
+
+        for error in &self.rejected {
+            writeln!(f, "rejected {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Ranks every hand that parses under `rules`, keeping the others as rejected lines.
+pub fn explain(input: &str, rules: &Rules) -> Report {
+    let (hands, rejected): (Vec<_>, Vec<_>) = parse_lines(input).partition_result();
+    rank(&hands, rules, rejected)
+}
+
+/// Ranks `hands` under `rules`, adding those the rules reject to `rejected`.
+fn rank(hands: &[Hand], rules: &Rules, mut rejected: Vec<ParseError>) -> Report {
+    let mut draws = vec![];
+    for hand in hands {
+        match rules.draw(hand) {
+            Ok(draw) => draws.push((draw, hand)),
+            Err(error) => rejected.push(error),
+        }
+    }
+    draws.sort_by(|a, b| a.0.cmp(&b.0));
+    rejected.sort_by_key(ParseError::line);
+
+    let entries = draws
+        .into_iter()
         .enumerate()
-        .map(|(i, Hand { draw: _, bid })| bid * (i as i64 + 1))
-        .sum())
+        .map(|(i, (draw, hand))| Entry {
+            line: hand.line,
+            cards: hand.cards().to_string(),
+            kind: draw.kind,
+            substitute: rules.substitute(&hand.cards().chars().collect::<Vec<_>>()),
+            rank: i + 1,
+            bid: hand.bid,
+            winnings: hand.bid * (i as i64 + 1),
+        })
+        .collect();
+
+    Report { entries, rejected }
+}
+
+pub fn part_one(input: &str) -> Result<i64> {
+    explain(input, &Rules::standard()).winnings()
 }
 
 pub fn part_two(input: &str) -> Result<i64> {
-    let mapping = HashMap::from([
-        ('2', 0),
-        ('3', 1),
-        ('4', 2),
-        ('5', 3),
-        ('6', 4),
-        ('7', 5),
-        ('8', 6),
-        ('9', 7),
-        ('T', 8),
-        ('J', -1),
-        ('Q', 10),
-        ('K', 11),
-        ('A', 12),
-    ]);
-
-    Ok(hands(input, &mapping)?
-        .iter()
-        .enumerate()
-        .map(|(i, Hand { draw: _, bid })| bid * (i as i64 + 1))
-        .sum())
+    explain(input, &Rules::jokers()).winnings()
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Vec<Hand>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        hands(input)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(winnings(input, &Rules::standard())?.into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(winnings(input, &Rules::jokers())?.into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
     use anyhow::Result;
 
     #[test]
     fn part_1_example() -> Result<()> {
-        let input = include_str!("../res/07.example");
-        assert_eq!(part_one(input)?, 6440);
+        let input = load(7, Variant::Example(1))?;
+        assert_eq!(part_one(&input)?, 6440);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/07.actual");
-        assert_eq!(part_one(input)?, 246_163_188);
+        let input = load(7, Variant::Actual)?;
+        assert_eq!(part_one(&input)?, 246_163_188);
         Ok(())
     }
 
     #[test]
     fn part_2_example() -> Result<()> {
-        let input = include_str!("../res/07.example");
-        assert_eq!(part_two(input)?, 5905);
+        let input = load(7, Variant::Example(1))?;
+        assert_eq!(part_two(&input)?, 5905);
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/07.actual");
-        assert_eq!(part_two(input)?, 245_794_069);
+        let input = load(7, Variant::Actual)?;
+        assert_eq!(part_two(&input)?, 245_794_069);
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() {
+        let error = part_one("32T3K 765\nT55X5 684\n").unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(
+            (error.day(), error.line(), error.column()),
+            (Some(7), 2, Some(4))
+        );
+        assert!(part_one("32T3K 765\nKK677\n").is_err());
+        assert!(part_one("32T3K 765\nKK6772 28\n").is_err());
+    }
+
+    #[test]
+    fn rules() -> Result<()> {
+        let classify =
+            |rules: &Rules, cards: &str| rules.classify(&cards.chars().collect::<Vec<_>>());
+        assert_eq!(classify(&Rules::standard(), "KTJJT"), Kind::TwoPair);
+        assert_eq!(classify(&Rules::jokers(), "KTJJT"), Kind::FourKind);
+        assert_eq!(classify(&Rules::jokers(), "JJJJJ"), Kind::FiveKind);
+        assert_eq!(classify(&Rules::jokers(), "2345J"), Kind::OnePair);
+
+        let wild = Rules::new("J23456789TQKA", "J2", 5)?;
+        assert_eq!(classify(&wild, "2J345"), Kind::ThreeKind);
+        assert_eq!(classify(&wild, "2J3J3"), Kind::FiveKind);
+
+        let small = Rules::new("23456789TJQKA", "", 3)?;
+        assert_eq!(winnings(&hands("AA2 10\n22K 5\n")?, &small)?, 25);
+        assert!(winnings(&hands("AA22 10\n")?, &small).is_err());
+
+        assert!(Rules::new("23456789TJQKA", "", 0).is_err());
+        assert!(Rules::new("23456789TJQKAA", "", 5).is_err());
+        assert!(Rules::new("23456789TJQKA", "*", 5).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn report() -> Result<()> {
+        let input = load(7, Variant::Example(1))?;
+        let report = explain(&format!("{}QQQ 1\nT55X5 2\n", input), &Rules::jokers());
+        assert_eq!(report.total(), 5905);
+        assert_eq!(report.entries.len(), 5);
+
+        let strongest = &report.entries[4];
+        assert_eq!(
+            (
+                strongest.cards.as_str(),
+                strongest.kind,
+                strongest.substitute
+            ),
+            ("KTJJT", Kind::FourKind, Some('T'))
+        );
+        assert_eq!((strongest.rank, strongest.winnings), (5, 1100));
+
+        let rejected: Vec<usize> = report.rejected.iter().map(|e| e.line()).collect();
+        assert_eq!(rejected, [6, 7]);
+        assert!(report
+            .to_string()
+            .contains("rejected day 7, line 7, column 4"));
+        assert!(report.winnings().is_err());
+
+        let report = explain(&format!("\n{}\n", input), &Rules::jokers());
+        assert_eq!(report.winnings()?, 5905);
+        assert_eq!(part_two(&format!("\n{}\n", input))?, 5905);
         Ok(())
     }
 }
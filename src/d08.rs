@@ -1,6 +1,10 @@
-use anyhow::{anyhow, bail, Context, Result};
+use crate::error::{self, Line, ParseError};
+use crate::{Answer, Solution};
+use anyhow::{bail, Context, Result};
 use std::collections::HashMap;
 
+const DAY: u8 = 8;
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Waypoint {
     key: [char; 3],
@@ -8,36 +12,38 @@ pub struct Waypoint {
     right: [char; 3],
 }
 
-impl TryFrom<&str> for Waypoint {
-    type Error = anyhow::Error;
+impl Waypoint {
+    fn parse(line: &Line) -> Result<Self, ParseError> {
+        fn label(line: &Line, text: &str) -> Result<[char; 3], ParseError> {
+            text.chars()
+                .collect::<Vec<char>>()
+                .try_into()
+                .map_err(|_| line.malformed(text, "a three character label"))
+        }
 
-    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
-        let (key, directions) = value
+        let (key, directions) = line
+            .text()
             .split_once(" = (")
-            .with_context(|| "unexpected file format")?;
+            .ok_or_else(|| line.malformed(line.text(), "\"<key> = (<left>, <right>)\""))?;
 
         let (left, right) = directions
             .trim_end_matches(')')
             .split_once(", ")
-            .with_context(|| "unexpected file format")?;
+            .ok_or_else(|| line.malformed(directions, "\"(<left>, <right>)\""))?;
 
-        let key: [char; 3] = key
-            .chars()
-            .collect::<Vec<char>>()
-            .try_into()
-            .map_err(|_| anyhow!("invalid key"))?;
-        let left: [char; 3] = left
-            .chars()
-            .collect::<Vec<char>>()
-            .try_into()
-            .map_err(|_| anyhow!("invalid left value"))?;
-        let right: [char; 3] = right
-            .chars()
-            .collect::<Vec<char>>()
-            .try_into()
-            .map_err(|_| anyhow!("invalid right value"))?;
+        Ok(Waypoint {
+            key: label(line, key)?,
+            left: label(line, left)?,
+            right: label(line, right)?,
+        })
+    }
+}
+
+impl TryFrom<&str> for Waypoint {
+    type Error = ParseError;
 
-        Ok(Waypoint { key, left, right })
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        Waypoint::parse(&Line::new(Some(DAY), 1, value))
     }
 }
 
@@ -48,20 +54,21 @@ pub struct Map {
 }
 
 impl TryFrom<&str> for Map {
-    type Error = anyhow::Error;
+    type Error = ParseError;
 
     fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
-        let mut lines = value.lines();
+        let mut lines = error::lines(DAY, value);
 
         let instructions = lines
             .next()
-            .with_context(|| "unexpected file format")?
+            .ok_or_else(|| ParseError::missing(DAY, 1, "instructions"))?
+            .text()
             .chars()
             .collect();
 
         let waypoints = lines
-            .filter(|l| !l.is_empty())
-            .map(|l| Waypoint::try_from(l))
+            .filter(|l| !l.text().is_empty())
+            .map(|l| Waypoint::parse(&l))
             .map(|w| w.map(|w| (w.key, w)))
             .collect::<Result<HashMap<_, _>, _>>()?;
 
@@ -72,8 +79,7 @@ impl TryFrom<&str> for Map {
     }
 }
 
-pub fn part_one(input: &str) -> Result<usize> {
-    let map = Map::try_from(input)?;
+fn walk(map: &Map) -> Result<usize> {
     let terminus: [char; 3] = ['Z', 'Z', 'Z'];
     let mut location = ['A', 'A', 'A'];
     for i in 0.. {
@@ -96,12 +102,11 @@ pub fn part_one(input: &str) -> Result<usize> {
     bail!("hops exceeded");
 }
 
-pub fn part_two(input: &str) -> Result<usize> {
-    let map = Map::try_from(input)?;
+fn haunt(map: &Map) -> Result<usize> {
     let mut locations: Vec<[char; 3]> = map
         .waypoints
-        .iter()
-        .map(|(k, _)| *k)
+        .keys()
+        .copied()
         .filter(|e| e.ends_with(&['A']))
         .collect();
 
@@ -142,36 +147,63 @@ pub fn part_two(input: &str) -> Result<usize> {
     Ok(product * map.instructions.len())
 }
 
+pub fn part_one(input: &str) -> Result<usize> {
+    walk(&Map::try_from(input)?)
+}
+
+pub fn part_two(input: &str) -> Result<usize> {
+    haunt(&Map::try_from(input)?)
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Map;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Ok(Map::try_from(input)?)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(walk(input)?.into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(haunt(input)?.into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
     use anyhow::Result;
 
     #[test]
     fn part_1_example() -> Result<()> {
-        let input = include_str!("../res/08-1.example");
-        assert_eq!(part_one(input)?, 2);
+        let input = load(8, Variant::Example(1))?;
+        assert_eq!(part_one(&input)?, 2);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/08.actual");
-        assert_eq!(part_one(input)?, 16_043);
+        let input = load(8, Variant::Actual)?;
+        assert_eq!(part_one(&input)?, 16_043);
         Ok(())
     }
 
     #[test]
     fn part_2_example() -> Result<()> {
-        let input = include_str!("../res/08-2.example");
-        assert_eq!(part_two(input)?, 6);
+        let input = load(8, Variant::Example(2))?;
+        assert_eq!(part_two(&input)?, 6);
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/08.actual");
-        assert_eq!(part_two(input)?, 15_726_453_850_399);
+        let input = load(8, Variant::Actual)?;
+        assert_eq!(part_two(&input)?, 15_726_453_850_399);
         Ok(())
     }
 }
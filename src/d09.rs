@@ -1,21 +1,28 @@
-use anyhow::{Context, Result};
+use crate::error;
+use crate::{Answer, Solution};
+use anyhow::Result;
 use itertools::Itertools;
 
+const DAY: u8 = 9;
+
 fn readings(input: &str) -> Result<Vec<Vec<i64>>> {
-    input
-        .lines()
+    Ok(error::lines(DAY, input)
         .map(|line| {
-            line.split_whitespace()
-                .map(|value| value.parse::<i64>())
+            line.text()
+                .split_whitespace()
+                .map(|value| {
+                    value
+                        .parse::<i64>()
+                        .map_err(|_| line.malformed(value, "a reading"))
+                })
                 .collect::<Result<_, _>>()
         })
-        .collect::<Result<_, _>>()
-        .with_context(|| "unexpected format")
+        .collect::<Result<_, _>>()?)
 }
 
-fn predict(readings: &Vec<i64>) -> i64 {
+fn predict(readings: &[i64]) -> i64 {
     if readings.iter().all_equal() {
-        return readings.last().map(|&e| e).unwrap_or_default();
+        return readings.last().copied().unwrap_or_default();
     }
 
     let next = predict(
@@ -23,15 +30,15 @@ fn predict(readings: &Vec<i64>) -> i64 {
             .iter()
             .tuple_windows()
             .map(|(&a, &b)| b - a)
-            .collect(),
+            .collect::<Vec<i64>>(),
     );
 
     readings.last().map(|&e| e + next).unwrap_or_default()
 }
 
-fn extrapolate(readings: &Vec<i64>) -> i64 {
+fn extrapolate(readings: &[i64]) -> i64 {
     if readings.iter().all(|&reading| reading == 0) {
-        return readings.first().map(|&e| e).unwrap_or_default();
+        return readings.first().copied().unwrap_or_default();
     }
 
     let next = extrapolate(
@@ -39,56 +46,77 @@ fn extrapolate(readings: &Vec<i64>) -> i64 {
             .iter()
             .tuple_windows()
             .map(|(&a, &b)| b - a)
-            .collect(),
+            .collect::<Vec<i64>>(),
     );
 
     readings.first().map(|&e| e - next).unwrap_or_default()
 }
 
+fn predictions(readings: &[Vec<i64>]) -> i64 {
+    readings.iter().map(|readings| predict(readings)).sum()
+}
+
+fn extrapolations(readings: &[Vec<i64>]) -> i64 {
+    readings.iter().map(|readings| extrapolate(readings)).sum()
+}
+
 pub fn part_one(input: &str) -> Result<i64> {
-    Ok(readings(input)?
-        .iter()
-        .map(|readings| predict(readings))
-        .sum())
+    Ok(predictions(&readings(input)?))
 }
 
 pub fn part_two(input: &str) -> Result<i64> {
-    Ok(readings(input)?
-        .iter()
-        .map(|readings| extrapolate(readings))
-        .sum())
+    Ok(extrapolations(&readings(input)?))
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Vec<Vec<i64>>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        readings(input)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(predictions(input).into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(extrapolations(input).into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
     use anyhow::Result;
 
     #[test]
     fn part_1_example() -> Result<()> {
-        let input = include_str!("../res/09.example");
-        assert_eq!(part_one(input)?, 114);
+        let input = load(9, Variant::Example(1))?;
+        assert_eq!(part_one(&input)?, 114);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/09.actual");
-        assert_eq!(part_one(input)?, 1_708_206_096);
+        let input = load(9, Variant::Actual)?;
+        assert_eq!(part_one(&input)?, 1_708_206_096);
         Ok(())
     }
 
     #[test]
     fn part_2_example() -> Result<()> {
-        let input = include_str!("../res/09.example");
-        assert_eq!(part_two(input)?, 2);
+        let input = load(9, Variant::Example(1))?;
+        assert_eq!(part_two(&input)?, 2);
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/09.actual");
-        assert_eq!(part_two(input)?, 1050);
+        let input = load(9, Variant::Actual)?;
+        assert_eq!(part_two(&input)?, 1050);
         Ok(())
     }
 }
@@ -1,5 +1,10 @@
+use crate::error;
+use crate::grid::Grid;
+use crate::{Answer, Solution};
 use anyhow::{bail, Result};
 
+const DAY: u8 = 10;
+
 #[derive(Debug, Clone, Copy)]
 pub enum Direction {
     T = 0b0001,
@@ -22,6 +27,15 @@ impl Direction {
         self.next().next().next()
     }
 
+    fn offset(self) -> (isize, isize) {
+        match self {
+            Direction::T => (-1, 0),
+            Direction::R => (0, 1),
+            Direction::B => (1, 0),
+            Direction::L => (0, -1),
+        }
+    }
+
     fn pipe(self, direction: Direction) -> Option<Pipe> {
         match (self, direction) {
             (Direction::T, Direction::B) => Some(Pipe::NS),
@@ -67,13 +81,14 @@ impl From<char> for Pipe {
             '7' => Pipe::SW,
             'F' => Pipe::SE,
             'S' => Pipe::ST,
+            // Only ground is left once `map` has checked the tiles.
             _ => Pipe::BL,
         }
     }
 }
 
 #[derive(Debug, Clone, Copy)]
-struct Tile {
+pub struct Tile {
     row: usize,
     column: usize,
     pipe: Pipe,
@@ -114,71 +129,65 @@ impl Tile {
     }
 }
 
-fn map(data: &str) -> Vec<Vec<Tile>> {
-    data.lines()
-        .enumerate()
-        .map(|(i, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(j, pipe)| Tile::new(i, j, pipe))
-                .collect::<Vec<Tile>>()
-        })
-        .collect()
-}
+fn map(data: &str) -> Result<Grid<Tile>> {
+    for line in error::lines(DAY, data) {
+        if let Some((i, c)) = line
+            .text()
+            .char_indices()
+            .find(|(_, c)| !"|-LJ7FS.".contains(*c))
+        {
+            return Err(line
+                .malformed_at(i, c.len_utf8(), "a pipe, ground or start")
+                .into());
+        }
+    }
 
-fn origin(map: &Vec<Vec<Tile>>) -> Option<&Tile> {
-    map.iter()
-        .flat_map(|row| row.iter())
-        .filter(|tile| tile.origin)
-        .next()
+    let pipes: Grid<char> = Grid::try_from(data).map_err(|e| e.on_day(DAY))?;
+    Grid::new(
+        pipes.width(),
+        pipes.height(),
+        pipes
+            .iter()
+            .map(|((row, column), &pipe)| Tile::new(row, column, pipe))
+            .collect(),
+    )
 }
 
-fn neighbour(tiles: &Vec<Vec<Tile>>, tile: Tile, direction: Direction) -> (Direction, Tile) {
-    match direction {
-        Direction::T => clip(tiles, tile.index(), -1, 0),
-        Direction::R => clip(tiles, tile.index(), 0, 1),
-        Direction::B => clip(tiles, tile.index(), 1, 0),
-        Direction::L => clip(tiles, tile.index(), 0, -1),
-    }
-    .map_or_else(
-        || neighbour(tiles, tile, direction.next()),
-        |&candidate| {
-            if tile.pipe.connected(candidate.pipe, direction) {
-                (direction, candidate.connect())
-            } else {
-                neighbour(tiles, tile, direction.next())
-            }
-        },
-    )
+fn origin(map: &Grid<Tile>) -> Option<&Tile> {
+    map.iter().map(|(_, tile)| tile).find(|tile| tile.origin)
 }
 
-fn clip(tiles: &Vec<Vec<Tile>>, index: (usize, usize), row: isize, column: isize) -> Option<&Tile> {
-    let (r, c) = index;
-    let row = r.checked_add_signed(row);
-    let column = c.checked_add_signed(column);
-    if let Some((row, column)) = row.and_then(|r| column.map(|c| (r, c))) {
-        tiles.get(row).and_then(|r| r.get(column))
-    } else {
-        None
-    }
+fn neighbour(tiles: &Grid<Tile>, tile: Tile, direction: Direction) -> (Direction, Tile) {
+    tiles
+        .neighbour(tile.index(), direction.offset())
+        .map_or_else(
+            || neighbour(tiles, tile, direction.next()),
+            |&candidate| {
+                if tile.pipe.connected(candidate.pipe, direction) {
+                    (direction, candidate.connect())
+                } else {
+                    neighbour(tiles, tile, direction.next())
+                }
+            },
+        )
 }
 
-fn create(input: &str) -> Result<Vec<Vec<Tile>>> {
-    let mut map = map(input);
+fn create(input: &str) -> Result<Grid<Tile>> {
+    let mut map = map(input)?;
     if let Some(&origin) = origin(&map) {
         let mut direction = Direction::T;
         let mut tile = origin;
 
         let (first, _) = neighbour(&map, tile, direction);
         let (last, _) = neighbour(&map, tile, first.next());
-        map[tile.row][tile.column] = tile.link(first, last);
+        map[tile.index()] = tile.link(first, last);
 
         loop {
             (direction, tile) = neighbour(&map, tile, direction.previous());
             if tile.origin {
                 return Ok(map);
             } else {
-                map[tile.row][tile.column] = tile;
+                map[tile.index()] = tile;
             }
         }
     }
@@ -186,68 +195,100 @@ fn create(input: &str) -> Result<Vec<Vec<Tile>>> {
     bail!("could not locate origin")
 }
 
-pub fn part_one(input: &str) -> Result<usize> {
-    let map = create(input)?;
-    let network: Vec<&Tile> = map
-        .iter()
-        .flat_map(|row| row.iter())
-        .filter(|tile| tile.connected)
-        .collect();
-    Ok((network.len() + 1) / 2)
+fn furthest(map: &Grid<Tile>) -> usize {
+    let network = map.iter().filter(|(_, tile)| tile.connected).count();
+    network.div_ceil(2)
 }
 
-pub fn part_two(input: &str) -> Result<usize> {
-    let map = create(input)?;
+fn enclosed(map: &Grid<Tile>) -> usize {
     let mut count = 0;
-    for row in map {
+    for row in map.rows() {
         let mut breached = false;
         let mut pending = 0;
         for tile in row {
             if tile.connected {
                 if tile.pipe.northbound() {
                     breached = !breached;
-                    count = count + pending;
+                    count += pending;
                     pending = 0;
                 }
             } else if breached {
-                pending = pending + 1;
+                pending += 1;
             }
         }
     }
 
-    Ok(count)
+    count
+}
+
+pub fn part_one(input: &str) -> Result<usize> {
+    Ok(furthest(&create(input)?))
+}
+
+pub fn part_two(input: &str) -> Result<usize> {
+    Ok(enclosed(&create(input)?))
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Grid<Tile>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        create(input)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(furthest(input).into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(enclosed(input).into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::ParseError;
+    use crate::input::{load, Variant};
     use anyhow::Result;
 
     #[test]
     fn part_1_example() -> Result<()> {
-        let input = include_str!("../res/10.example");
-        assert_eq!(part_one(input)?, 8);
+        let input = load(10, Variant::Example(1))?;
+        assert_eq!(part_one(&input)?, 8);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/10.actual");
-        assert_eq!(part_one(input)?, 6903);
+        let input = load(10, Variant::Actual)?;
+        assert_eq!(part_one(&input)?, 6903);
         Ok(())
     }
 
     #[test]
     fn part_2_example() -> Result<()> {
-        let input = include_str!("../res/10.example");
-        assert_eq!(part_two(input)?, 1);
+        let input = load(10, Variant::Example(1))?;
+        assert_eq!(part_two(&input)?, 1);
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/10.actual");
-        assert_eq!(part_two(input)?, 265);
+        let input = load(10, Variant::Actual)?;
+        assert_eq!(part_two(&input)?, 265);
         Ok(())
     }
+
+    #[test]
+    fn malformed() {
+        let error = part_one(".S-7.\n.|.|.\n.L?J.\n").unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(
+            (error.day(), error.line(), error.column(), error.snippet()),
+            (Some(10), 3, Some(3), Some("?"))
+        );
+    }
 }
@@ -1,29 +1,33 @@
+use crate::grid::Grid;
+use crate::{Answer, Solution};
+use anyhow::Result;
 use std::collections::HashSet;
 
+const DAY: u8 = 11;
 const STAR: char = '#';
 
-fn distances(input: &str, multiplier: usize) -> usize {
-    let width = input.chars().position(|c| c == '\n').unwrap_or(0);
-    let mut rows: HashSet<usize> = (0..width).collect();
-    let mut columns: HashSet<usize> = (0..input.len() / width).collect();
+fn image(input: &str) -> Result<Grid<char>> {
+    Ok(Grid::try_from(input).map_err(|e| e.on_day(DAY))?)
+}
 
-    let galaxy: Vec<(usize, usize)> = input
-        .lines()
+fn distances(image: &Grid<char>, multiplier: usize) -> usize {
+    let rows: HashSet<usize> = image
+        .rows()
         .enumerate()
-        .flat_map(|(r, line)| {
-            line.chars()
-                .enumerate()
-                .filter_map(|(c, feature)| {
-                    if feature == STAR {
-                        rows.remove(&r);
-                        columns.remove(&c);
-                        Some((r, c))
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<(usize, usize)>>()
-        })
+        .filter(|(_, row)| !row.contains(&STAR))
+        .map(|(r, _)| r)
+        .collect();
+
+    let columns: HashSet<usize> = image
+        .columns()
+        .enumerate()
+        .filter_map(|(c, mut column)| (!column.any(|&feature| feature == STAR)).then_some(c))
+        .collect();
+
+    let galaxy: Vec<(usize, usize)> = image
+        .iter()
+        .filter(|&(_, &feature)| feature == STAR)
+        .map(|(index, _)| index)
         .collect();
 
     let galaxy: Vec<(usize, usize)> = galaxy
@@ -52,44 +56,63 @@ fn empty_count(empties: &HashSet<usize>, index: &usize) -> usize {
     empties.iter().filter(|&i| i < index).count()
 }
 
-pub fn part_one(input: &str) -> usize {
-    distances(input, 1)
+pub fn part_one(input: &str) -> Result<usize> {
+    Ok(distances(&image(input)?, 1))
 }
 
-pub fn part_two(input: &str) -> usize {
-    distances(input, 999_999)
+pub fn part_two(input: &str) -> Result<usize> {
+    Ok(distances(&image(input)?, 999_999))
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Grid<char>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        image(input)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(distances(input, 1).into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(distances(input, 999_999).into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
     use anyhow::Result;
 
     #[test]
     fn part_1_example() -> Result<()> {
-        let input = include_str!("../res/11.example");
-        assert_eq!(part_one(input), 374);
+        let input = load(11, Variant::Example(1))?;
+        assert_eq!(part_one(&input)?, 374);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/11.actual");
-        assert_eq!(part_one(input), 9_647_174);
+        let input = load(11, Variant::Actual)?;
+        assert_eq!(part_one(&input)?, 9_647_174);
         Ok(())
     }
 
     #[test]
     fn part_2_example() -> Result<()> {
-        let input = include_str!("../res/11.example");
-        assert_eq!(part_two(input), 82_000_210);
+        let input = load(11, Variant::Example(1))?;
+        assert_eq!(part_two(&input)?, 82_000_210);
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/11.actual");
-        assert_eq!(part_two(input), 377_318_892_554);
+        let input = load(11, Variant::Actual)?;
+        assert_eq!(part_two(&input)?, 377_318_892_554);
         Ok(())
     }
 }
@@ -1,6 +1,10 @@
-use anyhow::{Context, Result};
+use crate::error::{self, ParseError};
+use crate::{Answer, Solution};
+use anyhow::Result;
 use std::collections::HashMap;
 
+const DAY: u8 = 12;
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 enum Spring {
     Unknown,
@@ -20,23 +24,15 @@ impl From<char> for Spring {
 
 impl Spring {
     fn damaged(&self) -> bool {
-        match self {
-            Self::Damaged => true,
-            Self::Unknown => true,
-            _ => false,
-        }
+        matches!(self, Self::Damaged | Self::Unknown)
     }
 
     fn fine(&self) -> bool {
-        match self {
-            Self::Fine => true,
-            Self::Unknown => true,
-            _ => false,
-        }
+        matches!(self, Self::Fine | Self::Unknown)
     }
 }
 
-struct Evaluation {
+pub struct Evaluation {
     runs: Vec<usize>,
     springs: Vec<Spring>,
 }
@@ -127,94 +123,134 @@ impl Evaluation {
     }
 }
 
-pub fn part_one(data: &str) -> Result<i64> {
-    data.lines()
-        .filter_map(|line| line.split_once(' '))
-        .map(|(springs, totals)| {
+fn evaluations(data: &str) -> Result<Vec<Evaluation>, ParseError> {
+    error::lines(DAY, data)
+        .map(|line| {
+            let (springs, totals) = line
+                .text()
+                .split_once(' ')
+                .ok_or_else(|| line.malformed(line.text(), "\"<springs> <runs>\""))?;
+
+            if let Some((i, c)) = springs.char_indices().find(|(_, c)| !"?#.".contains(*c)) {
+                return Err(line.malformed(&springs[i..i + c.len_utf8()], "a spring"));
+            }
+
             let runs = totals
                 .split(',')
-                .map(|total| total.parse::<usize>())
-                .collect::<Result<Vec<_>, _>>()
-                .with_context(|| "invalid total")?;
-
-            let springs: Vec<Spring> = springs.chars().map(|spring| Spring::from(spring)).collect();
+                .map(|total| {
+                    total
+                        .parse::<usize>()
+                        .map_err(|_| line.malformed(total, "a run length"))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
 
-            Ok(Evaluation { runs, springs }.permutations(0, 0, &mut HashMap::new()))
+            Ok(Evaluation {
+                runs,
+                springs: springs.chars().map(Spring::from).collect(),
+            })
         })
-        .sum::<Result<i64>>()
+        .collect()
 }
 
-pub fn part_two(data: &str) -> Result<i64> {
-    data.lines()
-        .filter_map(|line| line.split_once(' '))
-        .map(|(springs, totals)| {
-            let runs: Vec<usize> = totals
-                .split(',')
-                .map(|total| total.parse::<usize>())
-                .collect::<Result<Vec<_>, _>>()
-                .with_context(|| "invalid total")?;
+fn arrangements(evaluations: &[Evaluation]) -> i64 {
+    evaluations
+        .iter()
+        .map(|evaluation| evaluation.permutations(0, 0, &mut HashMap::new()))
+        .sum()
+}
 
-            let runs: Vec<usize> = (0..5).flat_map(|_| &runs).copied().collect::<Vec<_>>();
+fn unfolded(evaluations: &[Evaluation]) -> i64 {
+    evaluations
+        .iter()
+        .map(|Evaluation { runs, springs }| {
+            let runs: Vec<usize> = (0..5).flat_map(|_| runs).copied().collect::<Vec<_>>();
 
             let springs: Vec<Spring> = springs
-                .chars()
-                .map(|spring| Spring::from(spring))
-                .chain([Spring::Unknown].into_iter())
+                .iter()
+                .copied()
+                .chain([Spring::Unknown])
                 .cycle()
                 .take(springs.len() * 5 + 4)
                 .collect();
 
-            Ok(Evaluation { runs, springs }.permutations(0, 0, &mut HashMap::new()))
+            Evaluation { runs, springs }.permutations(0, 0, &mut HashMap::new())
         })
-        .sum::<Result<i64>>()
+        .sum()
+}
+
+pub fn part_one(data: &str) -> Result<i64> {
+    Ok(arrangements(&evaluations(data)?))
+}
+
+pub fn part_two(data: &str) -> Result<i64> {
+    Ok(unfolded(&evaluations(data)?))
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Vec<Evaluation>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Ok(evaluations(input)?)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(arrangements(input).into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(unfolded(input).into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
     use anyhow::Result;
 
     #[test]
     fn part_1_sample() -> Result<()> {
-        let sample: &str = concat!(
-            "???.### 1,1,3\n",
-            ".??..??...?##. 1,1,3\n",
-            "?#?#?#?#?#?#?#? 1,3,1,6\n",
-            "????.#...#... 4,1,1\n",
-            "????.######..#####. 1,6,5\n",
-            "?###???????? 3,2,1"
-        );
-
-        assert_eq!(part_one(sample)?, 21);
+        let sample = load(12, Variant::Example(1))?;
+        assert_eq!(part_one(&sample)?, 21);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/12");
-        assert_eq!(part_one(input)?, 7307);
+        let input = load(12, Variant::Actual)?;
+        assert_eq!(part_one(&input)?, 7307);
         Ok(())
     }
 
     #[test]
     fn part_2_sample() -> Result<()> {
-        let sample: &str = concat!(
-            "???.### 1,1,3\n",
-            ".??..??...?##. 1,1,3\n",
-            "?#?#?#?#?#?#?#? 1,3,1,6\n",
-            "????.#...#... 4,1,1\n",
-            "????.######..#####. 1,6,5\n",
-            "?###???????? 3,2,1"
-        );
-
-        assert_eq!(part_two(sample)?, 525_152);
+        let sample = load(12, Variant::Example(1))?;
+        assert_eq!(part_two(&sample)?, 525_152);
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/12");
-        assert_eq!(part_two(input)?, 3_415_570_893_842);
+        let input = load(12, Variant::Actual)?;
+        assert_eq!(part_two(&input)?, 3_415_570_893_842);
         Ok(())
     }
+
+    #[test]
+    fn malformed() {
+        let error = part_one("???.### 1,1,3\n.??..??...?##.\n").unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(
+            (error.day(), error.line(), error.column()),
+            (Some(12), 2, Some(1))
+        );
+        let error = part_one("???.### 1,x,3\n").unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!((error.column(), error.snippet()), (Some(11), Some("x")));
+        let error = part_one("??é.### 1,1,3\n").unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!((error.column(), error.snippet()), (Some(3), Some("é")));
+    }
 }
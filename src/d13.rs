@@ -1,21 +1,27 @@
+use crate::grid::Grid;
+use crate::{Answer, Solution};
+use anyhow::Result;
 use std::{cmp, collections::HashMap};
 
+const DAY: u8 = 13;
+
 fn compare(left: &[char], right: &[char]) -> usize {
     let mut count = 0;
     for i in 0..cmp::min(left.len(), right.len()) {
         if left[left.len() - i - 1] != right[i] {
-            count = count + 1;
+            count += 1;
         }
     }
     count
 }
 
-fn mirror(data: &Vec<Vec<char>>, width: usize, errors: usize) -> Option<usize> {
+fn mirror(data: &Grid<char>, errors: usize) -> Option<usize> {
+    let width = data.width();
     let mut counts: HashMap<usize, usize> = (1..width).map(|i| (i, 0)).collect();
-    for row in data.iter() {
+    for row in data.rows() {
         for i in 1..width {
             let compare = compare(&row[..i], &row[i..]);
-            counts.entry(i).and_modify(|count| (*count += compare));
+            counts.entry(i).and_modify(|count| *count += compare);
         }
     }
 
@@ -32,105 +38,99 @@ fn mirror(data: &Vec<Vec<char>>, width: usize, errors: usize) -> Option<usize> {
     }
 }
 
-pub fn symmetry(data: &str, errors: usize) -> usize {
-    let mut result = 0;
-
-    if let Some(width) = data.find("\n") {
-        let data: Vec<char> = data.split("\n").flat_map(|row| row.chars()).collect();
-        let length = data.len();
-
-        let first: Vec<Vec<char>> = (0..length / width)
-            .map(|i| (0..width).map(|j| data[(i * width) + j]).collect())
-            .collect();
+fn patterns(data: &str) -> Result<Vec<Grid<char>>> {
+    let mut offset = 0;
+    let mut patterns = vec![];
+    for pattern in data.split("\n\n") {
+        patterns.push(Grid::try_from(pattern).map_err(|e| e.offset(offset).on_day(DAY))?);
+        // Each pattern's own lines, then the blank line separating it from the next.
+        offset += pattern.matches('\n').count() + 2;
+    }
+    Ok(patterns)
+}
 
-        result = mirror(&first, width, errors)
-            .map(|value| result + value)
-            .unwrap_or(result);
+pub fn symmetry(pattern: &Grid<char>, errors: usize) -> usize {
+    let columns = mirror(pattern, errors).unwrap_or(0);
+    let rows = mirror(&pattern.transpose(), errors).map_or(0, |value| value * 100);
 
-        let second: Vec<Vec<char>> = (0..width)
-            .map(|i| (0..length / width).map(|j| data[i + (j * width)]).collect())
-            .collect();
+    columns + rows
+}
 
-        result = mirror(&second, length / width, errors)
-            .map(|value| result + value * 100)
-            .unwrap_or(result);
-    }
+fn summarize(patterns: &[Grid<char>], errors: usize) -> usize {
+    patterns
+        .iter()
+        .map(|pattern| symmetry(pattern, errors))
+        .sum()
+}
 
-    result
+pub fn part_one(data: &str) -> Result<usize> {
+    Ok(summarize(&patterns(data)?, 0))
 }
 
-pub fn part_one(data: &str) -> usize {
-    data.split("\n\n").map(|chart| symmetry(chart, 0)).sum()
+pub fn part_two(data: &str) -> Result<usize> {
+    Ok(summarize(&patterns(data)?, 1))
 }
 
-pub fn part_two(data: &str) -> usize {
-    data.split("\n\n").map(|chart| symmetry(chart, 1)).sum()
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Vec<Grid<char>>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        patterns(input)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(summarize(input, 0).into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(summarize(input, 1).into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::ParseError;
+    use crate::input::{load, Variant};
     use anyhow::Result;
 
     #[test]
     fn part_1_example() -> Result<()> {
-        let input = concat!(
-            "#.##..##.\n",
-            "..#.##.#.\n",
-            "##......#\n",
-            "##......#\n",
-            "..#.##.#.\n",
-            "..##..##.\n",
-            "#.#.##.#.\n",
-            "\n",
-            "#...##..#\n",
-            "#....#..#\n",
-            "..##..###\n",
-            "#####.##.\n",
-            "#####.##.\n",
-            "..##..###\n",
-            "#....#..#\n",
-        );
-
-        assert_eq!(part_one(input), 405);
+        let input = load(13, Variant::Example(1))?;
+        assert_eq!(part_one(&input)?, 405);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/13");
-        assert_eq!(part_one(input), 31_956);
+        let input = load(13, Variant::Actual)?;
+        assert_eq!(part_one(&input)?, 31_956);
         Ok(())
     }
 
     #[test]
     fn part_2_example() -> Result<()> {
-        let input = concat!(
-            "#.##..##.\n",
-            "..#.##.#.\n",
-            "##......#\n",
-            "##......#\n",
-            "..#.##.#.\n",
-            "..##..##.\n",
-            "#.#.##.#.\n",
-            "\n",
-            "#...##..#\n",
-            "#....#..#\n",
-            "..##..###\n",
-            "#####.##.\n",
-            "#####.##.\n",
-            "..##..###\n",
-            "#....#..#\n",
-        );
-
-        assert_eq!(part_two(input), 400);
+        let input = load(13, Variant::Example(1))?;
+        assert_eq!(part_two(&input)?, 400);
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/13");
-        assert_eq!(part_two(input), 37_617);
+        let input = load(13, Variant::Actual)?;
+        assert_eq!(part_two(&input)?, 37_617);
         Ok(())
     }
+
+    #[test]
+    fn malformed() {
+        let error = part_one("#.#\n..#\n\n##.\n#.\n").unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(
+            (error.day(), error.line(), error.snippet()),
+            (Some(13), 5, Some("#."))
+        );
+    }
 }
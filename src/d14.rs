@@ -1,92 +1,145 @@
+use crate::grid::Grid;
+use crate::{Answer, Solution};
 use anyhow::Result;
+use std::collections::HashMap;
 
-pub fn part_one(data: &str) -> usize {
-    if let Some(width) = data.find("\n") {
-        let data: Vec<char> = data.split("\n").flat_map(|row| row.chars()).collect();
-        let length = data.len();
-        let height = length / width;
-
-        (0..width)
-            .flat_map(|c| {
-                let mut weights = vec![];
-                (0..height).fold(0, |last, r| {
-                    let i = c + (r * width);
-                    if data[i] == 'O' {
-                        weights.push(height - last);
-                        last + 1
-                    } else if data[i] == '#' {
-                        r + 1
-                    } else {
-                        last
-                    }
-                });
-
-                weights
-            })
-            .sum()
-    } else {
-        0
+const DAY: u8 = 14;
+
+fn platform(data: &str) -> Result<Grid<char>> {
+    Ok(Grid::try_from(data).map_err(|e| e.on_day(DAY))?)
+}
+
+fn tilted_load(platform: &Grid<char>) -> usize {
+    let height = platform.height();
+
+    platform
+        .columns()
+        .flat_map(|column| {
+            let mut weights = vec![];
+            column.enumerate().fold(0, |last, (r, &rock)| {
+                if rock == 'O' {
+                    weights.push(height - last);
+                    last + 1
+                } else if rock == '#' {
+                    r + 1
+                } else {
+                    last
+                }
+            });
+
+            weights
+        })
+        .sum()
+}
+
+pub fn part_one(data: &str) -> Result<usize> {
+    Ok(tilted_load(&platform(data)?))
+}
+
+const CYCLES: usize = 1_000_000_000;
+
+fn tilt(platform: &mut Grid<char>) {
+    for c in 0..platform.width() {
+        let mut last = 0;
+        for r in 0..platform.height() {
+            match platform[(r, c)] {
+                'O' => {
+                    platform[(r, c)] = '.';
+                    platform[(last, c)] = 'O';
+                    last += 1;
+                }
+                '#' => last = r + 1,
+                _ => {}
+            }
+        }
     }
 }
 
-pub fn part_two(_input: &str) -> Result<i64> {
-    Ok(0)
+fn spin(platform: Grid<char>) -> Grid<char> {
+    (0..4).fold(platform, |mut platform, _| {
+        tilt(&mut platform);
+        platform.rotate()
+    })
+}
+
+fn load(platform: &Grid<char>) -> usize {
+    platform
+        .iter()
+        .filter(|&(_, &rock)| rock == 'O')
+        .map(|((r, _), _)| platform.height() - r)
+        .sum()
+}
+
+fn spun_load(platform: &Grid<char>) -> usize {
+    let mut platform = platform.clone();
+    let mut seen: HashMap<Grid<char>, usize> = HashMap::new();
+
+    for i in 0..CYCLES {
+        if let Some(start) = seen.insert(platform.clone(), i) {
+            let remaining = (CYCLES - i) % (i - start);
+            platform = (0..remaining).fold(platform, |platform, _| spin(platform));
+            break;
+        }
+
+        platform = spin(platform);
+    }
+
+    load(&platform)
+}
+
+pub fn part_two(data: &str) -> Result<usize> {
+    Ok(spun_load(&platform(data)?))
+}
+
+pub struct Solver;
+
+impl Solution for Solver {
+    type Input = Grid<char>;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        platform(input)
+    }
+
+    fn part_one(input: &Self::Input) -> Result<Answer> {
+        Ok(tilted_load(input).into())
+    }
+
+    fn part_two(input: &Self::Input) -> Result<Answer> {
+        Ok(spun_load(input).into())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{load, Variant};
     use anyhow::Result;
 
     #[test]
     fn part_1_example() -> Result<()> {
-        let input = concat!(
-            "O....#....\n",
-            "O.OO#....#\n",
-            ".....##...\n",
-            "OO.#O....O\n",
-            ".O.....O#.\n",
-            "O.#..O.#.#\n",
-            "..O..#O..O\n",
-            ".......O..\n",
-            "#....###..\n",
-            "#OO..#....\n",
-        );
-
-        assert_eq!(part_one(input), 136);
+        let input = load(14, Variant::Example(1))?;
+        assert_eq!(part_one(&input)?, 136);
         Ok(())
     }
 
     #[test]
     fn part_1_actual() -> Result<()> {
-        let input = include_str!("../res/14");
-        assert_eq!(part_one(input), 111_339);
+        let input = load(14, Variant::Actual)?;
+        assert_eq!(part_one(&input)?, 111_339);
         Ok(())
     }
 
     #[test]
     fn part_2_example() -> Result<()> {
-        let input = concat!(
-            "O....#....\n",
-            "O.OO#....#\n",
-            ".....##...\n",
-            "OO.#O....O\n",
-            ".O.....O#.\n",
-            "O.#..O.#.#\n",
-            "..O..#O..O\n",
-            ".......O..\n",
-            "#....###..\n",
-            "#OO..#....\n",
-        );
-
-        assert_eq!(part_two(input)?, 0);
+        let input = load(14, Variant::Example(1))?;
+        assert_eq!(part_two(&input)?, 64);
         Ok(())
     }
 
     #[test]
     fn part_2_actual() -> Result<()> {
-        let input = include_str!("../res/13");
-        assert_eq!(part_two(input)?, 0);
+        let input = load(14, Variant::Actual)?;
+        assert_eq!(part_two(&input)?, 93_736);
         Ok(())
     }
 }
@@ -0,0 +1,273 @@
+use std::fmt;
+
+/// Whether parsers may skip lines or tokens they can't interpret.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Every malformed line or token is reported as an error.
+    #[default]
+    Strict,
+    /// Malformed lines or tokens are skipped where the format allows it.
+    Lenient,
+}
+
+/// A parse failure positioned within the puzzle input.
+///
+/// Lines and columns are numbered from one. `day` is absent for errors raised by shared parsers
+/// such as [`crate::grid::Grid`] until a day tags them with [`ParseError::on_day`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// A token doesn't match what the format expects at its position.
+    Malformed {
+        day: Option<u8>,
+        line: usize,
+        column: usize,
+        snippet: String,
+        expected: String,
+    },
+    /// The input ends before a line the format requires.
+    Missing {
+        day: Option<u8>,
+        line: usize,
+        expected: String,
+    },
+}
+
+impl ParseError {
+    pub fn day(&self) -> Option<u8> {
+        match self {
+            ParseError::Malformed { day, .. } | ParseError::Missing { day, .. } => *day,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        match self {
+            ParseError::Malformed { line, .. } | ParseError::Missing { line, .. } => *line,
+        }
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            ParseError::Malformed { column, .. } => Some(*column),
+            ParseError::Missing { .. } => None,
+        }
+    }
+
+    pub fn snippet(&self) -> Option<&str> {
+        match self {
+            ParseError::Malformed { snippet, .. } => Some(snippet),
+            ParseError::Missing { .. } => None,
+        }
+    }
+
+    pub fn missing(day: u8, line: usize, expected: impl Into<String>) -> Self {
+        ParseError::Missing {
+            day: Some(day),
+            line,
+            expected: expected.into(),
+        }
+    }
+
+    /// Moves the error down by `lines`, for text parsed out of a larger input.
+    pub fn offset(self, lines: usize) -> Self {
+        match self {
+            ParseError::Malformed {
+                day,
+                line,
+                column,
+                snippet,
+                expected,
+            } => ParseError::Malformed {
+                day,
+                line: line + lines,
+                column,
+                snippet,
+                expected,
+            },
+            ParseError::Missing {
+                day,
+                line,
+                expected,
+            } => ParseError::Missing {
+                day,
+                line: line + lines,
+                expected,
+            },
+        }
+    }
+
+    /// Attributes the error to `day` unless it already belongs to one.
+    pub fn on_day(self, day: u8) -> Self {
+        match self {
+            ParseError::Malformed {
+                day: None,
+                line,
+                column,
+                snippet,
+                expected,
+            } => ParseError::Malformed {
+                day: Some(day),
+                line,
+                column,
+                snippet,
+                expected,
+            },
+            ParseError::Missing {
+                day: None,
+                line,
+                expected,
+            } => ParseError::Missing {
+                day: Some(day),
+                line,
+                expected,
+            },
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(day) = self.day() {
+            write!(f, "day {}, ", day)?;
+        }
+
+        match self {
+            ParseError::Malformed {
+                line,
+                column,
+                snippet,
+                expected,
+                ..
+            } => write!(
+                f,
+                "line {}, column {}: expected {} but found {:?}",
+                line, column, expected, snippet
+            ),
+            ParseError::Missing { line, expected, .. } => {
+                write!(
+                    f,
+                    "line {}: expected {} but the input ended",
+                    line, expected
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A numbered line of puzzle input used to position errors.
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    day: Option<u8>,
+    number: usize,
+    text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    pub fn new(day: Option<u8>, number: usize, text: &'a str) -> Self {
+        Line { day, number, text }
+    }
+
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// The byte offset of `snippet` within this line, or `None` when it wasn't sliced from it.
+    pub fn offset(&self, snippet: &str) -> Option<usize> {
+        let offset = (snippet.as_ptr() as usize).checked_sub(self.text.as_ptr() as usize)?;
+        let end = offset.checked_add(snippet.len())?;
+        (end <= self.text.len()).then_some(offset)
+    }
+
+    /// Reports the `length` bytes at byte `offset` as not matching `expected`.
+    ///
+    /// Panics when the bytes aren't characters of this line.
+    pub fn malformed_at(
+        &self,
+        offset: usize,
+        length: usize,
+        expected: impl Into<String>,
+    ) -> ParseError {
+        let snippet = &self.text[offset..offset + length];
+        let trimmed = snippet.trim_start();
+        let start = offset + snippet.len() - trimmed.len();
+        ParseError::Malformed {
+            day: self.day,
+            line: self.number,
+            column: self.text[..start].chars().count() + 1,
+            snippet: trimmed.trim_end().to_string(),
+            expected: expected.into(),
+        }
+    }
+
+    /// Reports `snippet` as not matching `expected`.
+    ///
+    /// Panics unless `snippet` was sliced from this line, so a column is never guessed.
+    pub fn malformed(&self, snippet: &str, expected: impl Into<String>) -> ParseError {
+        let offset = self
+            .offset(snippet)
+            .expect("snippet should be sliced from the line it's reported on");
+        self.malformed_at(offset, snippet.len(), expected)
+    }
+}
+
+/// Numbers the lines of `text` from one.
+pub fn lines(day: u8, text: &str) -> impl Iterator<Item = Line<'_>> {
+    text.lines()
+        .enumerate()
+        .map(move |(i, line)| Line::new(Some(day), i + 1, line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions() {
+        let line = lines(2, "first\nGame 7: 3 blue").nth(1).unwrap();
+        let error = line.malformed(&line.text()[8..], "a colour count");
+        assert_eq!(error.day(), Some(2));
+        assert_eq!(error.line(), 2);
+        assert_eq!(error.column(), Some(9));
+        assert_eq!(error.snippet(), Some("3 blue"));
+        assert_eq!(
+            error.to_string(),
+            "day 2, line 2, column 9: expected a colour count but found \"3 blue\""
+        );
+    }
+
+    #[test]
+    fn offsets() {
+        let text = String::from("ab é cd");
+        let line = Line::new(None, 4, &text);
+        assert_eq!(line.offset(&text[6..]), Some(6));
+        assert_eq!(line.offset(&String::from("c")), None);
+
+        let error = line.malformed_at(2, 3, "a letter");
+        assert_eq!((error.column(), error.snippet()), (Some(4), Some("é")));
+        assert_eq!(line.malformed(&text[6..], "x").column(), Some(6));
+    }
+
+    #[test]
+    #[should_panic]
+    fn foreign_snippets() {
+        let text = String::from("abc");
+        Line::new(None, 4, &text).malformed(&String::from("c"), "abc");
+    }
+
+    #[test]
+    fn tagging() {
+        let error = Line::new(None, 1, "x").malformed_at(0, 1, "y").on_day(10);
+        assert_eq!(error.day(), Some(10));
+        assert_eq!(error.on_day(11).day(), Some(10));
+        assert_eq!(
+            ParseError::missing(6, 2, "distances").to_string(),
+            "day 6, line 2: expected distances but the input ended"
+        );
+    }
+}
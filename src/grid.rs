@@ -0,0 +1,301 @@
+use crate::error::{Line, ParseError};
+use anyhow::{bail, Result};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// Row and column offsets of the four orthogonal neighbours: up, right, down, left.
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+/// Row and column offsets of all eight surrounding neighbours, clockwise from up-left.
+pub const SURROUNDING: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+];
+
+/// A rectangular grid stored row by row, indexed by `(row, column)`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Result<Self> {
+        if width * height != cells.len() {
+            bail!(
+                "expected {} cells for a {}x{} grid but found {}",
+                width * height,
+                width,
+                height,
+                cells.len()
+            );
+        }
+
+        Ok(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Parses one row per line, converting each character with `cell`.
+    pub fn parse_with(
+        text: &str,
+        mut cell: impl FnMut(char) -> T,
+    ) -> std::result::Result<Self, ParseError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = vec![];
+
+        for (row, line) in text.lines().enumerate() {
+            let length = line.chars().count();
+            match width {
+                Some(width) if width != length => {
+                    return Err(Line::new(None, row + 1, line)
+                        .malformed(line, format!("a row of {} columns", width)))
+                }
+                _ => width = Some(length),
+            }
+
+            cells.extend(line.chars().map(&mut cell));
+            height += 1;
+        }
+
+        Ok(Grid {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<&T> {
+        if row < self.height && column < self.width {
+            self.cells.get(row * self.width + column)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, row: usize, column: usize) -> Option<&mut T> {
+        if row < self.height && column < self.width {
+            self.cells.get_mut(row * self.width + column)
+        } else {
+            None
+        }
+    }
+
+    /// Moves `index` by `offset`, returning `None` when the result leaves the grid.
+    pub fn step(&self, index: (usize, usize), offset: (isize, isize)) -> Option<(usize, usize)> {
+        let row = index.0.checked_add_signed(offset.0)?;
+        let column = index.1.checked_add_signed(offset.1)?;
+        if row < self.height && column < self.width {
+            Some((row, column))
+        } else {
+            None
+        }
+    }
+
+    pub fn neighbour(&self, index: (usize, usize), offset: (isize, isize)) -> Option<&T> {
+        self.step(index, offset).map(|index| &self[index])
+    }
+
+    /// The in-bounds orthogonal neighbours of `index`.
+    pub fn neighbours(&self, index: (usize, usize)) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.around(index, &ORTHOGONAL)
+    }
+
+    /// The in-bounds orthogonal and diagonal neighbours of `index`.
+    pub fn surrounding(&self, index: (usize, usize)) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.around(index, &SURROUNDING)
+    }
+
+    fn around<'a>(
+        &'a self,
+        index: (usize, usize),
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = ((usize, usize), &'a T)> {
+        offsets
+            .iter()
+            .filter_map(move |&offset| self.step(index, offset))
+            .map(|index| (index, &self[index]))
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[T]> {
+        if row < self.height {
+            Some(&self.cells[row * self.width..(row + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).filter_map(|row| self.row(row))
+    }
+
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &T> {
+        self.cells
+            .iter()
+            .skip(column)
+            .step_by(self.width.max(1))
+            .take(if column < self.width { self.height } else { 0 })
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|column| self.column(column))
+    }
+
+    /// Every cell with its `(row, column)` index in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| ((i / self.width, i % self.width), cell))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    /// Swaps rows and columns.
+    pub fn transpose(&self) -> Self {
+        Grid {
+            width: self.height,
+            height: self.width,
+            cells: self.columns().flatten().cloned().collect(),
+        }
+    }
+
+    /// Rotates a quarter turn clockwise.
+    pub fn rotate(&self) -> Self {
+        Grid {
+            width: self.height,
+            height: self.width,
+            cells: (0..self.width)
+                .flat_map(|column| {
+                    (0..self.height)
+                        .rev()
+                        .map(move |row| self[(row, column)].clone())
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<T: From<char>> TryFrom<&str> for Grid<T> {
+    type Error = ParseError;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        Grid::parse_with(value, T::from)
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, column): (usize, usize)) -> &Self::Output {
+        self.get(row, column)
+            .unwrap_or_else(|| panic!("({}, {}) is outside the grid", row, column))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut Self::Output {
+        self.get_mut(row, column)
+            .unwrap_or_else(|| panic!("({}, {}) is outside the grid", row, column))
+    }
+}
+
+/// Renders one line per row, the inverse of parsing.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "abc\ndef\n";
+
+    #[test]
+    fn parse_and_render() -> Result<()> {
+        let grid: Grid<char> = Grid::try_from(SAMPLE)?;
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(1, 2)], 'f');
+        assert_eq!(grid.to_string(), SAMPLE);
+        let error = Grid::<char>::try_from("abc\nde\n").unwrap_err();
+        assert_eq!((error.line(), error.snippet()), (2, Some("de")));
+        assert_eq!(Grid::<char>::try_from("")?.height(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn neighbours() -> Result<()> {
+        let grid: Grid<char> = Grid::try_from(SAMPLE)?;
+        let orthogonal: String = grid.neighbours((0, 0)).map(|(_, &c)| c).collect();
+        assert_eq!(orthogonal, "bd");
+        let surrounding: String = grid.surrounding((1, 1)).map(|(_, &c)| c).collect();
+        assert_eq!(surrounding, "abcfd");
+        assert_eq!(grid.neighbour((0, 2), (0, 1)), None);
+        assert_eq!(grid.step((1, 0), (-1, 0)), Some((0, 0)));
+        Ok(())
+    }
+
+    #[test]
+    fn rows_and_columns() -> Result<()> {
+        let grid: Grid<char> = Grid::try_from(SAMPLE)?;
+        let rows: Vec<String> = grid.rows().map(String::from_iter).collect();
+        assert_eq!(rows, ["abc", "def"]);
+        let columns: Vec<String> = grid.columns().map(String::from_iter).collect();
+        assert_eq!(columns, ["ad", "be", "cf"]);
+        assert_eq!(grid.column(3).count(), 0);
+        assert_eq!(grid.row(2), None);
+        Ok(())
+    }
+
+    #[test]
+    fn transpose_and_rotate() -> Result<()> {
+        let grid: Grid<char> = Grid::try_from(SAMPLE)?;
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(grid.rotate().to_string(), "da\neb\nfc\n");
+        assert_eq!(grid.rotate().rotate().rotate().rotate(), grid);
+        assert_eq!(grid.transpose().transpose(), grid);
+        Ok(())
+    }
+}
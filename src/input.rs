@@ -0,0 +1,154 @@
+use anyhow::{bail, Context, Result};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+/// Overrides the directory puzzle inputs are resolved from.
+pub const DIRECTORY_VARIABLE: &str = "AOC_INPUTS";
+
+/// Which input of a day to load: one of the numbered examples or the actual puzzle input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Example(u8),
+    Actual,
+}
+
+impl Variant {
+    fn file_name(&self, day: u8) -> String {
+        match self {
+            Variant::Example(n) => format!("{:02}-{}.example", day, n),
+            Variant::Actual => format!("{:02}.actual", day),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Variant::Example(n) => write!(f, "example {}", n),
+            Variant::Actual => write!(f, "actual"),
+        }
+    }
+}
+
+impl TryFrom<&str> for Variant {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value.split_once(':') {
+            None if value == "actual" => Ok(Variant::Actual),
+            None if value == "example" => Ok(Variant::Example(1)),
+            Some(("example", n)) => n
+                .parse::<u8>()
+                .map(Variant::Example)
+                .with_context(|| format!("invalid example number {:?}", n)),
+            _ => bail!("unknown input variant {:?}", value),
+        }
+    }
+}
+
+/// Resolves inputs named `DD.actual` and `DD-N.example` inside a directory.
+#[derive(Debug, Clone)]
+pub struct Inputs {
+    directory: PathBuf,
+}
+
+impl Inputs {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Inputs {
+            directory: directory.into(),
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn path(&self, day: u8, variant: Variant) -> PathBuf {
+        self.directory.join(variant.file_name(day))
+    }
+
+    pub fn load(&self, day: u8, variant: Variant) -> Result<String> {
+        let path = self.path(day, variant);
+        fs::read_to_string(&path)
+            .map(|text| normalize(&text))
+            .with_context(|| {
+                format!(
+                    "missing {} input for day {}: couldn't read {}",
+                    variant,
+                    day,
+                    path.display()
+                )
+            })
+    }
+}
+
+impl Default for Inputs {
+    fn default() -> Self {
+        Inputs::new(
+            env::var_os(DIRECTORY_VARIABLE)
+                .map(PathBuf::from)
+                .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("res")),
+        )
+    }
+}
+
+/// Converts CRLF line endings to LF and terminates the text with exactly one newline.
+pub fn normalize(text: &str) -> String {
+    let mut text = text.replace("\r\n", "\n");
+    text.truncate(text.trim_end_matches('\n').len());
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
+
+/// Loads an input from the default directory.
+pub fn load(day: u8, variant: Variant) -> Result<String> {
+    Inputs::default().load(day, variant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_line_endings() {
+        assert_eq!(normalize("a\r\nb\r\n\r\n\n"), "a\nb\n");
+        assert_eq!(normalize("a\nb"), "a\nb\n");
+        assert_eq!(normalize("\n\n"), "");
+    }
+
+    #[test]
+    fn resolves_names() {
+        let inputs = Inputs::new("res");
+        assert_eq!(
+            inputs.path(8, Variant::Example(2)),
+            Path::new("res/08-2.example")
+        );
+        assert_eq!(inputs.path(14, Variant::Actual), Path::new("res/14.actual"));
+    }
+
+    #[test]
+    fn parses_variants() -> Result<()> {
+        assert_eq!(Variant::try_from("actual")?, Variant::Actual);
+        assert_eq!(Variant::try_from("example")?, Variant::Example(1));
+        assert_eq!(Variant::try_from("example:2")?, Variant::Example(2));
+        assert!(Variant::try_from("sample").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn reports_missing_files() {
+        let error = load(99, Variant::Example(3)).unwrap_err();
+        assert!(error.to_string().contains("day 99"));
+        assert!(error.to_string().contains("99-3.example"));
+    }
+
+    #[test]
+    fn loads_inputs() -> Result<()> {
+        assert!(load(8, Variant::Example(2))?.ends_with(")\n"));
+        assert!(!load(8, Variant::Example(2))?.ends_with("\n\n"));
+        Ok(())
+    }
+}
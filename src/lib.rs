@@ -1,3 +1,7 @@
+use anyhow::Result;
+use num_bigint::BigInt;
+use std::fmt;
+
 pub mod d01;
 pub mod d02;
 pub mod d03;
@@ -12,3 +16,146 @@ pub mod d11;
 pub mod d12;
 pub mod d13;
 pub mod d14;
+pub mod error;
+pub mod grid;
+pub mod input;
+
+/// A puzzle answer rendered the same way regardless of the day that produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(i128),
+    Text(String),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Number(value) => write!(f, "{}", value),
+            Answer::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+macro_rules! answer_from_number {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            fn from(value: $t) -> Self {
+                Answer::Number(value as i128)
+            }
+        })*
+    };
+}
+
+answer_from_number!(u32, u64, usize, i32, i64);
+
+/// Big integers that fit an `i128` stay numbers; larger ones are rendered as text.
+impl From<BigInt> for Answer {
+    fn from(value: BigInt) -> Self {
+        i128::try_from(&value).map_or_else(|_| Answer::Text(value.to_string()), Answer::Number)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(value: String) -> Self {
+        Answer::Text(value)
+    }
+}
+
+/// A single day of the calendar: a parse step followed by two parts sharing the parsed input.
+pub trait Solution {
+    type Input: 'static;
+
+    fn parse(input: &str) -> Result<Self::Input>;
+
+    fn part_one(input: &Self::Input) -> Result<Answer>;
+
+    fn part_two(input: &Self::Input) -> Result<Answer>;
+}
+
+/// The parsed input of a registered day with its type erased.
+pub trait Parsed {
+    fn part_one(&self) -> Result<Answer>;
+
+    fn part_two(&self) -> Result<Answer>;
+}
+
+struct Prepared<S: Solution>(S::Input);
+
+impl<S: Solution> Parsed for Prepared<S> {
+    fn part_one(&self) -> Result<Answer> {
+        S::part_one(&self.0)
+    }
+
+    fn part_two(&self) -> Result<Answer> {
+        S::part_two(&self.0)
+    }
+}
+
+fn prepare<S: Solution + 'static>(input: &str) -> Result<Box<dyn Parsed>> {
+    Ok(Box::new(Prepared::<S>(S::parse(input)?)))
+}
+
+/// A registry entry tying a day number to its solution.
+pub struct Day {
+    number: u8,
+    parse: fn(&str) -> Result<Box<dyn Parsed>>,
+}
+
+impl Day {
+    const fn new<S: Solution + 'static>(number: u8) -> Self {
+        Day {
+            number,
+            parse: prepare::<S>,
+        }
+    }
+
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Box<dyn Parsed>> {
+        (self.parse)(input)
+    }
+}
+
+pub static DAYS: &[Day] = &[
+    Day::new::<d01::Solver>(1),
+    Day::new::<d02::Solver>(2),
+    Day::new::<d03::Solver>(3),
+    Day::new::<d04::Solver>(4),
+    Day::new::<d05::Solver>(5),
+    Day::new::<d06::Solver>(6),
+    Day::new::<d07::Solver>(7),
+    Day::new::<d08::Solver>(8),
+    Day::new::<d09::Solver>(9),
+    Day::new::<d10::Solver>(10),
+    Day::new::<d11::Solver>(11),
+    Day::new::<d12::Solver>(12),
+    Day::new::<d13::Solver>(13),
+    Day::new::<d14::Solver>(14),
+];
+
+pub fn day(number: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|day| day.number == number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry() {
+        let numbers: Vec<u8> = DAYS.iter().map(|day| day.number()).collect();
+        assert_eq!(numbers, (1..=14).collect::<Vec<u8>>());
+        assert!(day(15).is_none());
+    }
+
+    #[test]
+    fn dispatch() -> Result<()> {
+        let day = day(9).expect("day 9 is registered");
+        let parsed = day.parse(&input::load(9, input::Variant::Example(1))?)?;
+        assert_eq!(parsed.part_one()?, Answer::from(114i64));
+        assert_eq!(parsed.part_two()?.to_string(), "2");
+        Ok(())
+    }
+}